@@ -4,13 +4,19 @@ use quote::quote;
 use syn::{parse_macro_input, FnArg, ItemFn, Pat, Stmt};
 
 pub fn valid_rule(text: &String) -> bool {
-    if text.is_empty() || text.as_bytes().len() > 16 {
+    if text.is_empty() || text.as_bytes().len() > 64 {
         return false;
     }
-    for char in text.chars() {
-        if !char.is_ascii_alphanumeric() {
+    // Hierarchical resources are allowed, e.g: "docs/finance/reports"
+    for segment in text.split('/') {
+        if segment.is_empty() {
             return false;
         }
+        for char in segment.chars() {
+            if !char.is_ascii_alphanumeric() && !matches!(char, '-' | '_' | '.' | ':') {
+                return false;
+            }
+        }
     }
 
     true
@@ -35,7 +41,7 @@ pub fn extract_rule(args: &TokenStream) -> (String, String) {
     }
     panic!(
         "The #[rule] macro requires the resource and permission params (only ASCII alphanumeric \
-               characters allowed). E.g:  #[rule(Comments, Write)]"
+               characters, \"-\", \"_\", \".\", \":\" and \"/\" separators allowed). E.g:  #[rule(Comments, Write)]"
     );
 }
 
@@ -102,6 +102,10 @@ pub fn sol_gateway_accounts_macro<'info>(_: TokenStream, item: TokenStream) -> T
             /// CHECK: Validated on CPI call
             pub sol_gateway_role: Option<UncheckedAccount<#lifetime>>
         }));
+        fields.named.push(parse_field(quote! {
+            /// CHECK: Validated on CPI call
+            pub sol_gateway_permissions: Option<UncheckedAccount<#lifetime>>
+        }));
         fields.named.push(parse_field(quote! {
             #[cfg_attr(not(test), account())]
             pub sol_gateway_token: Option<Box<Account<#lifetime, anchor_spl::token::TokenAccount>>>
@@ -118,6 +122,15 @@ pub fn sol_gateway_accounts_macro<'info>(_: TokenStream, item: TokenStream) -> T
             #[account(mut)]
             pub sol_gateway_seed: Option<UncheckedAccount<#lifetime>>
         }));
+        fields.named.push(parse_field(quote! {
+            /// CHECK: Validated on CPI call
+            #[account(mut)]
+            pub sol_gateway_audit: Option<UncheckedAccount<#lifetime>>
+        }));
+        fields.named.push(parse_field(quote! {
+            /// CHECK: Validated on CPI call
+            pub sol_gateway_config: UncheckedAccount<#lifetime>
+        }));
         fields.named.push(parse_field(quote! {
             pub sol_gateway: Program<#lifetime, SolCerberus>
         }));
@@ -145,6 +158,10 @@ pub fn sol_gateway_accounts_macro<'info>(_: TokenStream, item: TokenStream) -> T
                         None => None,
                         Some(x) => Some(x.to_account_info()),
                     },
+                    sol_gateway_permissions: match self.sol_gateway_permissions.as_ref() {
+                        None => None,
+                        Some(x) => Some(x.to_account_info()),
+                    },
                     sol_gateway_token: match self.sol_gateway_token.as_ref() {
                         None => None,
                         Some(x) => Some(x.to_account_info()),
@@ -157,10 +174,29 @@ pub fn sol_gateway_accounts_macro<'info>(_: TokenStream, item: TokenStream) -> T
                         None => None,
                         Some(x) => Some(x.to_account_info()),
                     },
+                    sol_gateway_audit: match self.sol_gateway_audit.as_ref() {
+                        None => None,
+                        Some(x) => Some(x.to_account_info()),
+                    },
+                    config: self.sol_gateway_config.to_account_info(),
                     system_program: self.#system_program_name.to_account_info(),
                 };
                 CpiContext::new(cpi_program, cpi_accounts)
             }
+
+            /// Checks access to the resource & permission (Rule namespace) without failing when denied.
+            pub fn sol_gateway_check(&self, file_id: Pubkey, resource: &str, permission: &str) -> Result<sol_gateway::instructions::AccessDecision> {
+                let decision = sol_gateway::cpi::check(
+                    self.sol_gateway_ctx(),
+                    sol_gateway::instructions::AllowedRule {
+                        file_id,
+                        namespace: 0 as u8, // Rule
+                        resource: resource.to_string(),
+                        permission: permission.to_string(),
+                    },
+                )?;
+                Ok(decision.get())
+            }
         }
     };
     // eprintln!("GENERATED:\r\n{:#?}", result.to_string());
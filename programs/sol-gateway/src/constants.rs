@@ -2,6 +2,16 @@ use anchor_lang::prelude::*;
 use solana_program::{declare_id, pubkey};
 
 pub const FEE: Option<u64> = Some(5000);
+/// Max length (in bytes) of Role, Resource and Permission names.
+pub const MAX_NAME_LENGTH: usize = 64;
+/// Max length (in bytes) of File metadata keys & values and max number of entries.
+pub const MAX_METADATA_KEY_LENGTH: usize = 32;
+pub const MAX_METADATA_VALUE_LENGTH: usize = 256;
+pub const MAX_METADATA_ENTRIES: usize = 32;
+/// Max length (in bytes) of the justification of a Role request.
+pub const MAX_JUSTIFICATION_LENGTH: usize = 256;
+/// Max number of namespaces still allowed while a File is frozen.
+pub const MAX_FROZEN_NAMESPACES: usize = 8;
 pub const PROGRAM_AUTHORITY: Pubkey = pubkey!("SCfVPLT34pep4pHfnMTzSyMZ2kLcxjKTGS2phuiApz5");
 
 pub mod metadata_program {
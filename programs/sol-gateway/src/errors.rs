@@ -4,9 +4,9 @@ use anchor_lang::error_code;
 pub enum Errors {
     #[msg("Only current Authority or Recovery accounts can update the File authority")]
     UnauthorizedAuthorityUpdate,
-    #[msg("Role, Resource or Permission must be betwen 1 and 16 alphanumeric characters long")]
+    #[msg("Role, Resource or Permission must be between 1 and 64 characters long (alphanumeric, '-', '_', '.' or ':')")]
     InvalidRule,
-    #[msg("Role must be between 1 and 16 alphanumeric characters long")]
+    #[msg("Role must be between 1 and 64 characters long (alphanumeric, '-', '_', '.' or ':')")]
     InvalidRole,
     #[msg("The provided string is too short")]
     StringTooShort,
@@ -36,4 +36,106 @@ pub enum Errors {
     FileMetadataAccountNotFound,
     #[msg("Metadata account provided when no metadata was expected")]
     UnexpectedMetadataAccount,
+    #[msg("The Permissions account is required to check rules granting a set of permissions")]
+    MissingPermissionsAccount,
+    #[msg("Permission sets can only be used on the Rule namespace and must include registered permissions only")]
+    InvalidPermissionSet,
+    #[msg("Maximum number of permissions reached (64)")]
+    TooManyPermissions,
+    #[msg("The permission is already registered")]
+    DuplicatedPermission,
+    #[msg("The Audit Log account is required when auditing is enabled on the File")]
+    MissingAuditAccount,
+    #[msg("The Rule and Role accounts are required (unless signed by the File authority)")]
+    MissingRuleOrRole,
+    #[msg("The Rule or Role belong to a different File")]
+    FileMismatch,
+    #[msg("The Rule belongs to a different namespace")]
+    NamespaceMismatch,
+    #[msg("The Rule does not apply to the requested resource")]
+    ResourceMismatch,
+    #[msg("The Rule does not grant the requested permission")]
+    PermissionMismatch,
+    #[msg("The Role is not the one granted by the Rule")]
+    RoleMismatch,
+    #[msg("The Role has expired")]
+    RoleExpired,
+    #[msg("The Rule has expired")]
+    RuleExpired,
+    #[msg("The token account is not owned by the signer")]
+    TokenNotOwned,
+    #[msg("The token account has no balance")]
+    EmptyTokenBalance,
+    #[msg("The Role is not assigned to the signer's wallet, NFT or collection")]
+    AddressMismatch,
+    #[msg("Metadata keys must be 1 to 32 characters long")]
+    InvalidMetadataKey,
+    #[msg("Metadata values (strings & bytes) can't be longer than 256 bytes")]
+    MetadataValueTooLong,
+    #[msg("The metadata key is duplicated")]
+    DuplicatedMetadataKey,
+    #[msg("Maximum number of metadata entries reached (32)")]
+    TooManyMetadataEntries,
+    #[msg("The metadata key was not found")]
+    MetadataKeyNotFound,
+    #[msg("A metadata key required by the File schema is missing")]
+    MissingMetadataKey,
+    #[msg("The metadata value type does not match the File schema")]
+    InvalidMetadataType,
+    #[msg("Schema max length can't exceed the max metadata value length (256)")]
+    InvalidSchemaMaxLength,
+    #[msg("The metadata schema account was not found")]
+    MetadataSchemaAccountNotFound,
+    #[msg("Metadata schema account provided when no schema was expected")]
+    UnexpectedMetadataSchemaAccount,
+    #[msg("Invalid cache type (0 => Roles, 1 => Rules)")]
+    InvalidCacheUpdated,
+    #[msg("The File already uses the current layout")]
+    FileAlreadyMigrated,
+    #[msg("The registry page is not the one expected")]
+    InvalidRegistryPage,
+    #[msg("The account was not found on the registry page")]
+    RegistryEntryNotFound,
+    #[msg("The registry account was not provided")]
+    MissingRegistryAccount,
+    #[msg("Maximum number of Roles of the File reached")]
+    TooManyRoles,
+    #[msg("Maximum number of Rules of the File reached")]
+    TooManyRules,
+    #[msg("The File still has Roles, Rules or other accounts, or its migration is not completed")]
+    FileNotEmpty,
+    #[msg("The File has been deleted")]
+    FileDeleted,
+    #[msg("The File has not been deleted")]
+    FileNotDeleted,
+    #[msg("The account does not belong to the deleted File")]
+    InvalidTeardownAccount,
+    #[msg("The File is frozen")]
+    FileFrozen,
+    #[msg("The File is not frozen")]
+    FileNotFrozen,
+    #[msg("Maximum number of namespaces allowed on a frozen File reached (8)")]
+    TooManyFrozenNamespaces,
+    #[msg("The program is paused")]
+    ProgramPaused,
+    #[msg("Only the wallet holding the Role can renounce it")]
+    NotRoleHolder,
+    #[msg("Claimed Roles must be valid for a positive duration")]
+    InvalidRoleDuration,
+    #[msg("Justification is too long (256 bytes max)")]
+    JustificationTooLong,
+    #[msg("The account is already listed on the File registry")]
+    AlreadyRegistered,
+    #[msg("The token has already claimed this Role and the claimed Role has not expired")]
+    TokenAlreadyClaimed,
+    #[msg("The rent of a closed account must be returned to its payer")]
+    InvalidRentReceiver,
+    #[msg("The File is not being migrated")]
+    FileNotMigrating,
+    #[msg("The wallet already holds this Role and it has not expired")]
+    RoleNotExpired,
+    #[msg("The File metadata is already stored with typed values")]
+    MetadataAlreadyMigrated,
+    #[msg("The File metadata is stored with the legacy layout, migrate it first")]
+    MetadataNotMigrated,
 }
@@ -1,21 +1,71 @@
+pub use add_permissions::*;
 pub use add_rule::*;
 pub use allowed::*;
+pub use approve_request::*;
 pub use assign_role::*;
+pub use claim_role::*;
+pub use close_file_accounts::*;
+pub use complete_migration::*;
 pub use delete_assigned_role::*;
 pub use delete_file::*;
+pub use delete_role_template::*;
 pub use delete_rule::*;
+pub use freeze_file::*;
 pub use initialize_files::*;
+pub use migrate_file::*;
+pub use migrate_metadata::*;
+pub use migrate_role::*;
+pub use migrate_rule::*;
+pub use register_role::*;
+pub use register_rule::*;
+pub use reject_request::*;
+pub use remove_metadata_entry::*;
+pub use renounce_role::*;
+pub use request_role::*;
+pub use set_metadata_entry::*;
+pub use set_metadata_schema::*;
+pub use set_pause::*;
+pub use set_role_template::*;
+pub use unfreeze_file::*;
+pub use update_audit::*;
 pub use update_cache::*;
 pub use update_file::*;
 pub use update_metadata::*;
+pub use update_role::*;
+pub use update_rule::*;
 
+pub mod add_permissions;
 pub mod add_rule;
 pub mod allowed;
+pub mod approve_request;
 pub mod assign_role;
+pub mod claim_role;
+pub mod close_file_accounts;
+pub mod complete_migration;
 pub mod delete_assigned_role;
 pub mod delete_file;
+pub mod delete_role_template;
 pub mod delete_rule;
+pub mod freeze_file;
 pub mod initialize_files;
+pub mod migrate_file;
+pub mod migrate_metadata;
+pub mod migrate_role;
+pub mod migrate_rule;
+pub mod register_role;
+pub mod register_rule;
+pub mod reject_request;
+pub mod remove_metadata_entry;
+pub mod renounce_role;
+pub mod request_role;
+pub mod set_metadata_entry;
+pub mod set_metadata_schema;
+pub mod set_pause;
+pub mod set_role_template;
+pub mod unfreeze_file;
+pub mod update_audit;
 pub mod update_cache;
 pub mod update_file;
 pub mod update_metadata;
+pub mod update_role;
+pub mod update_rule;
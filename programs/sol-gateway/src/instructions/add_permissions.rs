@@ -0,0 +1,71 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::File;
+use crate::state::permissions::*;
+use crate::utils::{allowed_authority, grow_account, utc_now, valid_rule};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct AddPermissions<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&authority.key(), &file.authority) @ Errors::Unauthorized,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PermissionRegistry::space(&[]),
+        seeds = [b"permissions".as_ref(), file.id.key().as_ref()],
+        bump
+    )]
+    pub permission_registry: Box<Account<'info, PermissionRegistry>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Registers new permissions on the File, so they can be granted in sets (bitmask) by a single Rule.
+pub fn add_permissions(ctx: Context<AddPermissions>, permissions_data: PermissionsData) -> Result<()> {
+    let registry = &mut ctx.accounts.permission_registry;
+    registry.file_id = ctx.accounts.file.id;
+    registry.bump = ctx.bumps.permission_registry;
+    ctx.accounts.file.add_child(&mut registry.payer, ctx.accounts.authority.key());
+    for permission in &permissions_data.permissions {
+        if !valid_rule(permission, false) {
+            return err!(Errors::InvalidRule);
+        }
+        if registry.permissions.contains(permission) {
+            return err!(Errors::DuplicatedPermission);
+        }
+        if registry.permissions.len() >= PermissionRegistry::MAX_PERMISSIONS {
+            return err!(Errors::TooManyPermissions);
+        }
+        registry.permissions.push(permission.clone());
+    }
+    grow_account(
+        &registry.to_account_info(),
+        PermissionRegistry::space(&registry.permissions),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit_event!(ctx, PermissionsChanged {
+        time: utc_now(),
+        file_id: ctx.accounts.file.id,
+        permissions: permissions_data.permissions,
+        actor: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
@@ -1,23 +1,18 @@
 use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
-use crate::instructions::allowed::{allowed, AllowedRule};
-use crate::utils::{valid_rules, utc_now, validate_ns_permission, roles::address_or_wildcard};
+use crate::instructions::allowed::{allowed, allowed_without_fee, AllowedRule};
+use crate::utils::{register, registry_page_index, valid_rule_data, rule_permissions, utc_now, validate_rule_resource, seeds::{rule_seed, role_seed}};
+use crate::state::config::ProgramConfig;
 use crate::state::role::Role;
+use crate::emit_event;
 use anchor_lang::prelude::*;
-use crate::state::file::{File, Seed};
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
+use crate::state::permissions::PermissionRegistry;
+use crate::state::registry::*;
 use crate::state::rule::*;
 use crate::Errors;
 use crate::metadata_program;
 
-// SPACE SIZE:
-// + 8 discriminator
-// + 32 file_id (Pubkey)
-// + 1 namespace (u8)
-// + 4 + 16 role (string)
-// + 4 + 16 resource (string)
-// + 4 + 16  permission (string)
-// + 1 + 8 expires_at Option<i64>
-// + 1 bump
-// total = 8 + 32 + 1 + 4 + 16 + 4 + 16 + 4 + 16  + 1 + 8 + 1 = 111
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 #[instruction(rule_data:RuleData)]
 pub struct AddRule<'info> {
@@ -26,29 +21,35 @@ pub struct AddRule<'info> {
     #[account(
         init,
         payer = signer,
-        space = 111,
-        seeds = [rule_data.namespace.to_le_bytes().as_ref(), rule_data.role.as_ref(), rule_data.resource.as_ref(), rule_data.permission.as_ref(), sol_gateway_file.id.key().as_ref()], 
-        constraint = valid_rules(&rule_data.role, &rule_data.resource, &rule_data.permission)  @ Errors::InvalidRule,
+        space = Rule::MAX_SIZE,
+        seeds = [b"rule".as_ref(), rule_seed(rule_data.namespace, &rule_data.role, &rule_data.resource, &rule_data.permission).as_ref(), sol_gateway_file.id.key().as_ref()], 
+        constraint = valid_rule_data(&rule_data.role, &rule_data.resource, &rule_data.permission, rule_data.permissions)  @ Errors::InvalidRule,
         bump
     )]
     pub rule: Account<'info, Rule>,
     #[account(
+        mut,
         seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
         bump = sol_gateway_file.bump,
     )]
     pub sol_gateway_file: Box<Account<'info, File>>,
     #[account(
-        seeds = [sol_gateway_role.role.as_ref(),  address_or_wildcard(&sol_gateway_role.address), sol_gateway_role.file_id.key().as_ref()],
+        seeds = [b"permissions".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = permission_registry.bump,
+    )]
+    pub permission_registry: Option<Box<Account<'info, PermissionRegistry>>>,
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
         bump = sol_gateway_role.bump
     )]
     pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
     #[account(
-        seeds = [sol_gateway_rule.namespace.to_le_bytes().as_ref(), sol_gateway_rule.role.as_ref(), sol_gateway_rule.resource.as_ref(), sol_gateway_rule.permission.as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
         bump = sol_gateway_rule.bump,
     )]
     pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
     #[account(
-        seeds = [sol_gateway_rule2.namespace.to_le_bytes().as_ref(), sol_gateway_rule2.role.as_ref(), sol_gateway_rule2.resource.as_ref(), sol_gateway_rule2.permission.as_ref(), sol_gateway_rule2.file_id.key().as_ref()],
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule2.namespace, &sol_gateway_rule2.role, &sol_gateway_rule2.resource, &sol_gateway_rule2.permission).as_ref(), sol_gateway_rule2.file_id.key().as_ref()],
         bump = sol_gateway_rule2.bump,
     )]
     pub sol_gateway_rule2: Option<Box<Account<'info, Rule>>>,
@@ -68,6 +69,30 @@ pub struct AddRule<'info> {
         bump
     )]
     pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Rules as u8], registry_page_index(registry.rules).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -75,12 +100,23 @@ pub fn add_rule(
     ctx: Context<AddRule>,
     data:RuleData
 ) -> Result<()> {
+    // Permission sets (bitmask) are only allowed on the "Rule" namespace.
+    if data.permissions != 0 && data.namespace != Namespaces::Rule as u8 {
+        return Err(error!(Errors::InvalidPermissionSet))
+    }
+    let permissions = rule_permissions(
+        &data.permission,
+        data.permissions,
+        ctx.accounts.permission_registry.as_deref().map(|registry| &**registry),
+    )?;
+
     // Checks if is allowed to add a rule for this specific Namespace and Role.
     allowed(
         &ctx.accounts.signer,
         &ctx.accounts.sol_gateway_file,
         &ctx.accounts.sol_gateway_role,
         &ctx.accounts.sol_gateway_rule,
+        &None,
         &ctx.accounts.sol_gateway_token,
         &ctx.accounts.sol_gateway_metadata,
         &mut ctx.accounts.sol_gateway_seed,
@@ -92,36 +128,46 @@ pub fn add_rule(
             permission: data.role.to_string(),
         },
     )?;
-    // // Checks if is allowed to add a rule for this specific Resource and Permission.
-    allowed(
-        &ctx.accounts.signer,
-        &ctx.accounts.sol_gateway_file,
-        &ctx.accounts.sol_gateway_role,
-        &ctx.accounts.sol_gateway_rule2,
-        &ctx.accounts.sol_gateway_token,
-        &ctx.accounts.sol_gateway_metadata,
-        &mut ctx.accounts.sol_gateway_seed,
-        &ctx.accounts.system_program,
-        AllowedRule {
+    // // Checks if is allowed to add a rule for this specific Resource and Permission (each one of them on permission sets).
+    // Resource and Permission can be patterns (e.g. "docs/*"), which are only allowed
+    // when covered by the signer's rule (e.g. "docs/*" covers "docs/finance/*" but not "*").
+    // The fee is only paid for the first permission of the set.
+    for (index, permission) in permissions.into_iter().enumerate() {
+        let allowed_rule = AllowedRule {
             file_id: ctx.accounts.sol_gateway_file.id.key(),
             namespace: Namespaces::AddRuleResourcePerm as u8,
             resource: data.resource.to_string(),
-            permission: data.permission.to_string(),
-        },
-    )?;
-
-    // Validate AddressType when creating "AssignRole" or "DeleteAssignRole" rules (Resource can only be Wallet, Nft, Collection or wildcard "*")
-    if data.namespace >= Namespaces::AssignRole as u8 && data.namespace <= Namespaces::DeleteAssignRole as u8 {
-        if !matches!(data.resource.as_str(), "Wallet" | "Nft" | "Collection" | "*") {
-                return Err(error!(Errors::InvalidAddressType))
+            permission,
+        };
+        if index == 0 {
+            allowed(
+                &ctx.accounts.signer,
+                &ctx.accounts.sol_gateway_file,
+                &ctx.accounts.sol_gateway_role,
+                &ctx.accounts.sol_gateway_rule2,
+                &None,
+                &ctx.accounts.sol_gateway_token,
+                &ctx.accounts.sol_gateway_metadata,
+                &mut ctx.accounts.sol_gateway_seed,
+                &ctx.accounts.system_program,
+                allowed_rule,
+            )?;
+        } else {
+            allowed_without_fee(
+                &ctx.accounts.signer,
+                &ctx.accounts.sol_gateway_file,
+                &ctx.accounts.sol_gateway_role,
+                &ctx.accounts.sol_gateway_rule2,
+                &None,
+                &ctx.accounts.sol_gateway_token,
+                &ctx.accounts.sol_gateway_metadata,
+                allowed_rule,
+            )?;
         }
     }
-    
-    // Validate Namespace when creating "AddRuleNSRole", "DeleteRuleNSRole" rules.
-    // The allowed namespace must be either an u8 number (0-255) or a wildcard "*"
-    if data.namespace == Namespaces::AddRuleNSRole as u8 && data.namespace == Namespaces::DeleteRuleNSRole as u8 {
-        validate_ns_permission(&data.resource)?;
-    }
+
+    // Validate the Resource of the rules managing Roles (AddressType) and Rules (namespace)
+    validate_rule_resource(data.namespace, &data.resource)?;
 
     // Add permission
     let rule = &mut ctx.accounts.rule;
@@ -131,10 +177,33 @@ pub fn add_rule(
     rule.role = data.role;
     rule.resource = data.resource;
     rule.permission = data.permission;
+    rule.permissions = data.permissions;
     rule.expires_at = data.expires_at;
-    emit!(RulesChanged {
-        time: utc_now(),
-        file_id: ctx.accounts.sol_gateway_file.id,
-    });
+    rule.registered = true;
+    rule.payer = ctx.accounts.signer.key();
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Created, &*ctx.accounts.rule)?;
+    ctx.accounts.sol_gateway_file.add_rule()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.signer.key(),
+        RegistryKind::Rules,
+        ctx.accounts.rule.key(),
+    )?;
+
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Rules, now);
+    }
+    emit_event!(ctx, RulesChanged::new(
+        now,
+        ChangeAction::Created,
+        ctx.accounts.rule.key(),
+        &ctx.accounts.rule,
+        ctx.accounts.signer.key(),
+    ));
     Ok(())
 }
@@ -1,11 +1,13 @@
 use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
-use crate::state::{File, Seed};
+use crate::state::config::ProgramConfig;
+use crate::state::{File, Seed, PermissionRegistry, AuditLog, AuditEntry};
 use crate::state::rule::Rule;
-use crate::utils::{allowed_perm, utc_now, address_or_wildcard, allowed_authority, get_fee, subtract_rent_exemption_from_fee};
+use crate::utils::{allowed_perm, rule_grants, utc_now, rule_seed, role_seed, allowed_authority, frozen, get_fee, subtract_rent_exemption_from_fee, truncate_string};
 use crate::state::role::Role;
 use crate::metadata_program;
 use anchor_lang::prelude::*;
-use crate::Errors::{Unauthorized, InvalidFileID, MissingSeedAccount};
+use crate::Errors::{self, MissingSeedAccount, MissingAuditAccount};
+use crate::MAX_NAME_LENGTH;
 
 
 #[derive(Accounts)]
@@ -18,15 +20,20 @@ pub struct Allowed<'info> {
     )]
     pub sol_gateway_file: Box<Account<'info, File>>,
     #[account(
-        seeds = [sol_gateway_rule.namespace.to_le_bytes().as_ref(), sol_gateway_rule.role.as_ref(), sol_gateway_rule.resource.as_ref(), sol_gateway_rule.permission.as_ref(), sol_gateway_rule.file_id.key().as_ref()], 
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()], 
         bump = sol_gateway_rule.bump,
     )]
     pub sol_gateway_rule: Option< Box<Account<'info, Rule>>>,
     #[account(
-        seeds = [sol_gateway_role.role.as_ref(), address_or_wildcard(&sol_gateway_role.address), sol_gateway_role.file_id.key().as_ref()], 
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()], 
         bump = sol_gateway_role.bump
     )]
     pub sol_gateway_role: Option< Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"permissions".as_ref(), sol_gateway_permissions.file_id.key().as_ref()],
+        bump = sol_gateway_permissions.bump,
+    )]
+    pub sol_gateway_permissions: Option< Box<Account<'info, PermissionRegistry>>>,
     #[account()]
     pub sol_gateway_token: Option< Box<Account<'info, TokenAccount>>>,
     #[account(
@@ -43,10 +50,23 @@ pub struct Allowed<'info> {
         bump
     )]
     pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    #[account(
+        mut,
+        seeds = [b"audit".as_ref(), sol_gateway_audit.file_id.key().as_ref()],
+        bump = sol_gateway_audit.bump,
+    )]
+    pub sol_gateway_audit: Option<Box<Account<'info, AuditLog>>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::checks_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct AllowedRule {
     pub file_id: Pubkey,
     pub namespace: u8,
@@ -54,94 +74,298 @@ pub struct AllowedRule {
     pub permission: String,
 }
 
+///  AccessReasons (why access was granted or denied):
+///     Authority => Granted to the File authority
+///     Granted => Granted by the provided Role & Rule
+///     MissingRuleOrRole => Rule or Role accounts were not provided
+///     FileMismatch => Rule or Role belong to a different File
+///     NamespaceMismatch => Rule belongs to a different Namespace
+///     ResourceMismatch => Rule does not match the Resource
+///     PermissionMismatch => Rule does not grant the Permission
+///     RoleMismatch => Role is not the one required by the Rule
+///     RoleExpired => Role has expired
+///     RuleExpired => Rule has expired
+///     TokenNotOwned => Signer is not the owner of the provided token account
+///     EmptyTokenBalance => The provided token account has no balance
+///     AddressMismatch => Role is not assigned to the signer (wallet, NFT or collection)
+///     FileDeleted => The File is being torn down (denied even to the File authority)
+///     FileFrozen => The File is frozen and the namespace is not allowed through
+///     InvalidFileID => The requested File is not the provided one
+///     MissingSeedAccount => The seed account (which collects the fee) was not provided
+///     MissingPermissionsAccount => The Rule has a permission set but the File permission registry was not provided
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum AccessReason {
+    Authority,
+    Granted,
+    MissingRuleOrRole,
+    FileMismatch,
+    NamespaceMismatch,
+    ResourceMismatch,
+    PermissionMismatch,
+    RoleMismatch,
+    RoleExpired,
+    RuleExpired,
+    TokenNotOwned,
+    EmptyTokenBalance,
+    AddressMismatch,
+    FileDeleted,
+    FileFrozen,
+    InvalidFileID,
+    MissingSeedAccount,
+    MissingPermissionsAccount,
+}
+
+/// Result of an access check (returned by the "check" instruction as return data).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct AccessDecision {
+    pub granted: bool,
+    pub reason: AccessReason,
+    pub rule: Option<Pubkey>, // Rule used on the check (when provided)
+    pub role_expires_at: Option<i64>,
+}
+
+impl AccessReason {
+    /// Error thrown by "allowed" when access is denied for this reason.
+    pub fn error(&self) -> Errors {
+        match self {
+            AccessReason::Authority | AccessReason::Granted => Errors::Unauthorized, // Never thrown (access granted)
+            AccessReason::MissingRuleOrRole => Errors::MissingRuleOrRole,
+            AccessReason::FileMismatch => Errors::FileMismatch,
+            AccessReason::NamespaceMismatch => Errors::NamespaceMismatch,
+            AccessReason::ResourceMismatch => Errors::ResourceMismatch,
+            AccessReason::PermissionMismatch => Errors::PermissionMismatch,
+            AccessReason::RoleMismatch => Errors::RoleMismatch,
+            AccessReason::RoleExpired => Errors::RoleExpired,
+            AccessReason::RuleExpired => Errors::RuleExpired,
+            AccessReason::TokenNotOwned => Errors::TokenNotOwned,
+            AccessReason::EmptyTokenBalance => Errors::EmptyTokenBalance,
+            AccessReason::AddressMismatch => Errors::AddressMismatch,
+            AccessReason::FileDeleted => Errors::FileDeleted,
+            AccessReason::FileFrozen => Errors::FileFrozen,
+            AccessReason::InvalidFileID => Errors::InvalidFileID,
+            AccessReason::MissingSeedAccount => Errors::MissingSeedAccount,
+            AccessReason::MissingPermissionsAccount => Errors::MissingPermissionsAccount,
+        }
+    }
+}
 
+impl AccessDecision {
+    pub fn new(reason: AccessReason, rule: Option<Pubkey>, role_expires_at: Option<i64>) -> Self {
+        Self {
+            granted: matches!(reason, AccessReason::Authority | AccessReason::Granted),
+            reason,
+            rule,
+            role_expires_at,
+        }
+    }
+}
+
+/// Throws the error matching the denial reason unless access is granted.
+#[allow(clippy::too_many_arguments)]
 pub fn allowed<'info>(
     signer: &Signer<'info>,
     file: &Box<Account<'info, File>>,
     role: &Option<Box<Account<'info, Role>>>,
     rule: &Option<Box<Account<'info, Rule>>>,
+    permissions: &Option<Box<Account<'info, PermissionRegistry>>>,
     token: &Option<Box<Account<'info, TokenAccount>>>,
     metadata: &Option<Box<Account<'info, MetadataAccount>>>,
     seed: &mut Option<Account<'info, Seed>>,
     system_program: &Program<'info, anchor_lang::system_program::System>,
     allowed_rule: AllowedRule) -> Result<()> {
-    // The FILE ID must be the one authorized by the program
-    if allowed_rule.file_id != file.id.key(){
-        return Err(error!(InvalidFileID))
+    let decision = check(signer, file, role, rule, permissions, token, metadata, seed, system_program, allowed_rule)?;
+    if !decision.granted {
+        return Err(error!(decision.reason.error()))
     }
-    
-    // FILE Authority is always allowed (No fees)
-    if allowed_authority(&signer.key(), &file.authority.key()){
-        return Ok(());
+    Ok(())
+}
+
+/// Same as "allowed" without paying the fee, used for the remaining permissions of a set
+/// (the fee is only paid once per instruction).
+#[allow(clippy::too_many_arguments)]
+pub fn allowed_without_fee<'info>(
+    signer: &Signer<'info>,
+    file: &Account<'info, File>,
+    role: &Option<Box<Account<'info, Role>>>,
+    rule: &Option<Box<Account<'info, Rule>>>,
+    permissions: &Option<Box<Account<'info, PermissionRegistry>>>,
+    token: &Option<Box<Account<'info, TokenAccount>>>,
+    metadata: &Option<Box<Account<'info, MetadataAccount>>>,
+    allowed_rule: AllowedRule) -> Result<()> {
+    let decision = match file_decision(signer, file, rule, &allowed_rule)? {
+        Some(decision) => decision,
+        None => evaluate(signer, file, role, rule, permissions, token, metadata, &allowed_rule)?,
+    };
+    if !decision.granted {
+        return Err(error!(decision.reason.error()))
+    }
+    Ok(())
+}
+
+/// Checks access without failing when denied (fee is only paid when granted).
+/// The seed account created through "init_if_needed" is closed again on denied checks, so its rent
+/// goes back to the signer instead of being charged for a call that was not granted.
+#[allow(clippy::too_many_arguments)]
+pub fn check<'info>(
+    signer: &Signer<'info>,
+    file: &Account<'info, File>,
+    role: &Option<Box<Account<'info, Role>>>,
+    rule: &Option<Box<Account<'info, Rule>>>,
+    permissions: &Option<Box<Account<'info, PermissionRegistry>>>,
+    token: &Option<Box<Account<'info, TokenAccount>>>,
+    metadata: &Option<Box<Account<'info, MetadataAccount>>>,
+    seed: &mut Option<Account<'info, Seed>>,
+    system_program: &Program<'info, anchor_lang::system_program::System>,
+    allowed_rule: AllowedRule) -> Result<AccessDecision> {
+    let decision = check_and_pay(signer, file, role, rule, permissions, token, metadata, seed, system_program, allowed_rule)?;
+    if !decision.granted {
+        if let Some(seed) = seed.as_ref() {
+            if !seed.initialized {
+                seed.close(signer.to_account_info())?;
+            }
+        }
+    }
+    Ok(decision)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_and_pay<'info>(
+    signer: &Signer<'info>,
+    file: &Account<'info, File>,
+    role: &Option<Box<Account<'info, Role>>>,
+    rule: &Option<Box<Account<'info, Rule>>>,
+    permissions: &Option<Box<Account<'info, PermissionRegistry>>>,
+    token: &Option<Box<Account<'info, TokenAccount>>>,
+    metadata: &Option<Box<Account<'info, MetadataAccount>>>,
+    seed: &mut Option<Account<'info, Seed>>,
+    system_program: &Program<'info, anchor_lang::system_program::System>,
+    allowed_rule: AllowedRule) -> Result<AccessDecision> {
+    if let Some(decision) = file_decision(signer, file, rule, &allowed_rule)? {
+        return Ok(decision);
     }
 
     let mut fee:  u64 = get_fee(file);
     // Seed account is mandatory when Fee is defined and using normal "Rule"
     if fee > 0 && seed.is_none() {
-        return Err(error!(MissingSeedAccount))
+        return Ok(AccessDecision::new(AccessReason::MissingSeedAccount, rule.as_ref().map(|rule| rule.key()), None));
+    }
+
+    let decision = evaluate(signer, file, role, rule, permissions, token, metadata, &allowed_rule)?;
+    if decision.granted {
+        // Initialize Seed account (if needed)
+        // First granted call on each wallet initializes a seed account which will be used to collect fees
+        // therefore the rent exemption fee from the account should be deducted from the regular fee.
+        if let Some(seed) = seed.as_mut() {
+            if !seed.initialized {
+                fee = subtract_rent_exemption_from_fee(fee);
+                seed.initialized = true;
+            }
+        }
+        pay_fee(system_program, signer, seed, fee)?;
+    }
+    Ok(decision)
+}
+
+/// Decision taken from the File alone (deleted, authority or frozen), no fees are paid on these.
+fn file_decision<'info>(
+    signer: &Signer<'info>,
+    file: &Account<'info, File>,
+    rule: &Option<Box<Account<'info, Rule>>>,
+    allowed_rule: &AllowedRule) -> Result<Option<AccessDecision>> {
+    // The FILE ID must be the one authorized by the program
+    if allowed_rule.file_id != file.id.key(){
+        return Ok(Some(AccessDecision::new(AccessReason::InvalidFileID, rule.as_ref().map(|rule| rule.key()), None)));
     }
 
-    // Initialize Seed account (if needed)
-    // First call to "allowed()" on each wallet initializes a seed account which will be used to collect fees
-    // therefore the rent exemption fee from the account should be deducted from the regular fee.
-    if seed.is_some() && !seed.as_ref().unwrap().initialized{
-        fee = subtract_rent_exemption_from_fee(fee);
-        seed.as_mut().map(|s| {
-            s.initialized = true;
-        });
+    // Deleted FILEs only accept the teardown of their accounts
+    if file.deleted_at.is_some() {
+        return Ok(Some(AccessDecision::new(AccessReason::FileDeleted, rule.as_ref().map(|rule| rule.key()), None)));
     }
 
+    // FILE Authority is always allowed (No fees)
+    if allowed_authority(&signer.key(), &file.authority.key()){
+        return Ok(Some(AccessDecision::new(AccessReason::Authority, rule.as_ref().map(|rule| rule.key()), None)));
+    }
+
+    // Frozen FILEs deny everything but the authority and the namespaces allowed through
+    if frozen(file, allowed_rule.namespace) {
+        return Ok(Some(AccessDecision::new(AccessReason::FileFrozen, rule.as_ref().map(|rule| rule.key()), None)));
+    }
+    Ok(None)
+}
+
+/// Evaluates the Role & Rule against the requested access.
+#[allow(clippy::too_many_arguments)]
+fn evaluate<'info>(
+    signer: &Signer<'info>,
+    file: &Account<'info, File>,
+    role: &Option<Box<Account<'info, Role>>>,
+    rule: &Option<Box<Account<'info, Rule>>>,
+    permissions: &Option<Box<Account<'info, PermissionRegistry>>>,
+    token: &Option<Box<Account<'info, TokenAccount>>>,
+    metadata: &Option<Box<Account<'info, MetadataAccount>>>,
+    allowed_rule: &AllowedRule) -> Result<AccessDecision> {
     // Rule or Role can only be empty when using Authority
     if rule.is_none() || role.is_none(){
-        return Err(error!(Unauthorized))
+        return Ok(AccessDecision::new(AccessReason::MissingRuleOrRole, rule.as_ref().map(|rule| rule.key()), None))
     }
 
     let rule = rule.as_ref().unwrap();
     let role = role.as_ref().unwrap();
+    let decision = |reason| AccessDecision::new(reason, Some(rule.key()), role.expires_at);
 
     // The FILE ID must match on: FILE, Role, Rule
     if file.id != rule.file_id  || file.id != role.file_id{
-        return Err(error!(Unauthorized))
+        return Ok(decision(AccessReason::FileMismatch))
     }
 
     // Check Rule is within the corresponding Namespace
     if rule.namespace != allowed_rule.namespace  {
-        return Err(error!(Unauthorized))
+        return Ok(decision(AccessReason::NamespaceMismatch))
     }
 
-    // Check Resource & Permission
-    if !allowed_perm(&allowed_rule.resource, &rule.resource) || !allowed_perm(&allowed_rule.permission, &rule.permission){
-        return Err(error!(Unauthorized))
+    // Check Resource & Permission (either a single Permission or a set of them)
+    if !allowed_perm(&allowed_rule.resource, &rule.resource) {
+        return Ok(decision(AccessReason::ResourceMismatch))
+    }
+    // Permission sets can only be checked against the File permission registry
+    match rule_grants(rule, &allowed_rule.permission, permissions.as_deref().map(|registry| &**registry)) {
+        Ok(true) => {}
+        Ok(false) => return Ok(decision(AccessReason::PermissionMismatch)),
+        Err(_) => return Ok(decision(AccessReason::MissingPermissionsAccount)),
     }
 
     // Check Role
     if role.role != rule.role {
-        return Err(error!(Unauthorized))
+        return Ok(decision(AccessReason::RoleMismatch))
     }
 
     let now = utc_now();
     // Check if role expired
     if role.expires_at.is_some() && role.expires_at.unwrap() <= now{
-        return Err(error!(Unauthorized))
+        return Ok(decision(AccessReason::RoleExpired))
     }
     // Check if rule expired 
     if rule.expires_at.is_some() && rule.expires_at.unwrap() <= now{
-        return Err(error!(Unauthorized))
+        return Ok(decision(AccessReason::RuleExpired))
     }
     // Check if the wallet is authorized (Address = "None" is considered wildcard "*")
       if role.address.is_none() || signer.key() == role.address.unwrap(){
-            return pay_fee(system_program, signer, seed, fee);
+            return Ok(decision(AccessReason::Granted));
     }
     // Check if the file or Collection Mint addresses are authorized
     if token.is_some(){
         let token = token.as_ref().unwrap();
         // Check if is the real owner of the file and has at least one
-        if token.owner != signer.key() || token.amount <= 0{
-            return Err(error!(Unauthorized))
+        if token.owner != signer.key() {
+            return Ok(decision(AccessReason::TokenNotOwned))
+        }
+        if token.amount == 0 {
+            return Ok(decision(AccessReason::EmptyTokenBalance))
         }
         // File authorized (Address = "None" is considered wildcard "*")
         if role.address.is_none() || token.mint == role.address.unwrap(){
-            return pay_fee(system_program, signer, seed, fee);
+            return Ok(decision(AccessReason::Granted));
         }
         if  metadata.is_some() {
             let metadata = metadata.as_ref().unwrap();
@@ -149,13 +373,44 @@ pub fn allowed<'info>(
                 let collection = metadata.collection.as_ref().unwrap();
                 // Collection authorized (Address = "None" is considered wildcard "*")
                 if collection.verified && (role.address.is_none() || collection.key == role.address.unwrap()){
-                    return pay_fee(system_program, signer, seed, fee);
+                    return Ok(decision(AccessReason::Granted));
                 }
             }
         }
     }
 
-    Err(error!(Unauthorized))
+    Ok(decision(AccessReason::AddressMismatch))
+}
+
+/// Records the successful access check on the File's audit log (when auditing is enabled)
+pub fn record_access<'info>(
+    signer: &Signer<'info>,
+    file: &Account<'info, File>,
+    role: &Option<Box<Account<'info, Role>>>,
+    audit: &mut Option<Box<Account<'info, AuditLog>>>,
+    allowed_rule: &AllowedRule) -> Result<()> {
+    if !file.audit {
+        return Ok(());
+    }
+    let audit = match audit {
+        Some(audit) if audit.file_id == file.id => audit,
+        _ => return Err(error!(MissingAuditAccount)),
+    };
+    // Role is left empty when the access was granted to the File authority
+    let role = match role {
+        Some(role) if !allowed_authority(&signer.key(), &file.authority.key()) => role.role.clone(),
+        _ => String::new(),
+    };
+    audit.record(AuditEntry {
+        signer: signer.key(),
+        namespace: allowed_rule.namespace,
+        // Requested names can be longer than Rule names when matched by patterns (e.g. "docs/*")
+        resource: truncate_string(&allowed_rule.resource, MAX_NAME_LENGTH),
+        permission: truncate_string(&allowed_rule.permission, MAX_NAME_LENGTH),
+        role,
+        time: utc_now(),
+    });
+    Ok(())
 }
 
 /// Pay fee (when defined)
@@ -0,0 +1,189 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
+use crate::state::registry::*;
+use crate::state::request::*;
+use crate::state::role::*;
+use crate::state::rule::{Namespaces, Rule};
+use crate::utils::{register, registry_page_index, seeds::{role_seed, rule_seed}, utc_now, wallet_roles_insert};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct ApproveRequest<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"request".as_ref(), role_seed(&request.role, &AddressType::Wallet, &Some(request.wallet)).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = request.bump,
+    )]
+    pub request: Account<'info, RoleRequest>,
+    /// CHECK: Wallet that requested the Role (receives the rent of the request)
+    #[account(
+        mut,
+        constraint = requester.key() == request.wallet @ Errors::Unauthorized,
+    )]
+    pub requester: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = signer,
+        space = Role::MAX_SIZE,
+        seeds = [b"role".as_ref(), role_seed(&request.role, &AddressType::Wallet, &Some(request.wallet)).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub role: Account<'info, Role>,
+
+    /** Validation accounts (only required when signer is not the Authority) */
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page_index(registry.roles).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = WalletRoles::space(1),
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), request.wallet.as_ref()],
+        bump
+    )]
+    pub wallet_roles: Box<Account<'info, WalletRoles>>,
+
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Assigns the requested Wallet Role (with the requested expiration) and closes the request.
+pub fn approve_request(ctx: Context<ApproveRequest>) -> Result<()> {
+    // File authority is always allowed, other signers require a role on the ReviewRequest namespace
+    allowed(
+        &ctx.accounts.signer,
+        &ctx.accounts.sol_gateway_file,
+        &ctx.accounts.sol_gateway_role,
+        &ctx.accounts.sol_gateway_rule,
+        &None,
+        &ctx.accounts.sol_gateway_token,
+        &ctx.accounts.sol_gateway_metadata,
+        &mut ctx.accounts.sol_gateway_seed,
+        &ctx.accounts.system_program,
+        AllowedRule {
+            file_id: ctx.accounts.sol_gateway_file.id,
+            namespace: Namespaces::ReviewRequest as u8,
+            resource: "request".to_string(),
+            permission: ctx.accounts.request.role.clone(),
+        },
+    )?;
+
+    let request = &ctx.accounts.request;
+    let role = &mut ctx.accounts.role;
+    role.bump = ctx.bumps.role;
+    role.file_id = ctx.accounts.sol_gateway_file.id;
+    role.address = Some(request.wallet);
+    role.role = request.role.clone();
+    role.address_type = AddressType::Wallet;
+    role.expires_at = request.expires_at;
+    role.registered = true;
+    role.payer = ctx.accounts.signer.key();
+
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Created, &*ctx.accounts.role)?;
+    ctx.accounts.sol_gateway_file.add_role()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.signer.key(),
+        RegistryKind::Roles,
+        ctx.accounts.role.key(),
+    )?;
+    let wallet_roles = &mut ctx.accounts.wallet_roles;
+    wallet_roles.file_id = ctx.accounts.sol_gateway_file.id;
+    wallet_roles.wallet = ctx.accounts.request.wallet;
+    wallet_roles.bump = ctx.bumps.wallet_roles;
+    ctx.accounts.sol_gateway_file.add_child(&mut wallet_roles.payer, ctx.accounts.signer.key());
+    wallet_roles_insert(
+        wallet_roles,
+        ctx.accounts.role.key(),
+        &ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    // The request is closed once approved
+    ctx.accounts.sol_gateway_file.remove_child();
+
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+    }
+    emit_event!(ctx, RolesChanged::new(
+        now,
+        ChangeAction::Created,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.signer.key(),
+    ));
+    emit_event!(ctx, RoleRequestChanged::new(
+        now,
+        RequestAction::Approved,
+        ctx.accounts.request.key(),
+        &ctx.accounts.request,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
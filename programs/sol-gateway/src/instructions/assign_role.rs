@@ -1,13 +1,17 @@
 use crate::instructions::allowed::{allowed, AllowedRule};
 use crate::metadata_program;
-use crate::state::file::{File, Seed};
+use crate::state::config::ProgramConfig;
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
 use crate::state::role::*;
 use crate::state::rule::{Namespaces, Rule};
-use crate::utils::{roles::address_or_wildcard, rules::*, utc_now};
-use crate::Errors::InvalidRole;
+use crate::state::registry::*;
+use crate::utils::{register, registry_page_index, rules::*, seeds::{role_seed, rule_seed}, utc_now, wallet_roles_insert};
+use crate::Errors::{self, InvalidRole};
+use crate::emit_event;
 use anchor_lang::prelude::*;
 use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 #[instruction(assign_role_data:AssignRoleData)]
 pub struct AssignRole<'info> {
@@ -17,8 +21,8 @@ pub struct AssignRole<'info> {
     #[account(
         init,
         payer = rent_payer,
-        space = 105,
-        seeds = [assign_role_data.role.as_ref(), address_or_wildcard(&assign_role_data.address), sol_gateway_file.id.key().as_ref()],
+        space = Role::MAX_SIZE,
+        seeds = [b"role".as_ref(), role_seed(&assign_role_data.role, &assign_role_data.address_type, &assign_role_data.address).as_ref(), sol_gateway_file.id.key().as_ref()],
         constraint = valid_rule(&assign_role_data.role, true) @ InvalidRole,
         bump
     )]
@@ -26,17 +30,18 @@ pub struct AssignRole<'info> {
 
     /** Validation accounts */
     #[account(
+        mut,
         seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
         bump = sol_gateway_file.bump,
     )]
     pub sol_gateway_file: Box<Account<'info, File>>,
     #[account(
-        seeds = [sol_gateway_role.role.as_ref(),  address_or_wildcard(&sol_gateway_role.address), sol_gateway_role.file_id.key().as_ref()],
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
         bump = sol_gateway_role.bump
     )]
     pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
     #[account(
-        seeds = [sol_gateway_rule.namespace.to_le_bytes().as_ref(), sol_gateway_rule.role.as_ref(), sol_gateway_rule.resource.as_ref(), sol_gateway_rule.permission.as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
         bump = sol_gateway_rule.bump,
     )]
     pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
@@ -60,6 +65,39 @@ pub struct AssignRole<'info> {
     #[account(mut)]
     pub rent_payer: Signer<'info>,
 
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page_index(registry.roles).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        space = WalletRoles::space(1),
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), assign_role_data.address.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub wallet_roles: Option<Box<Account<'info, WalletRoles>>>, // Required for "Wallet" Roles
+
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -69,6 +107,7 @@ pub fn assign_role(ctx: Context<AssignRole>, assign_role_data: AssignRoleData) -
         &ctx.accounts.sol_gateway_file,
         &ctx.accounts.sol_gateway_role,
         &ctx.accounts.sol_gateway_rule,
+        &None,
         &ctx.accounts.sol_gateway_token,
         &ctx.accounts.sol_gateway_metadata,
         &mut ctx.accounts.sol_gateway_seed,
@@ -88,10 +127,48 @@ pub fn assign_role(ctx: Context<AssignRole>, assign_role_data: AssignRoleData) -
     role.role = assign_role_data.role;
     role.address_type = assign_role_data.address_type;
     role.expires_at = assign_role_data.expires_at;
+    role.registered = true;
+    role.payer = ctx.accounts.rent_payer.key();
+
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Created, &*ctx.accounts.role)?;
+    ctx.accounts.sol_gateway_file.add_role()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.rent_payer.key(),
+        RegistryKind::Roles,
+        ctx.accounts.role.key(),
+    )?;
+    if let (AddressType::Wallet, Some(wallet)) = (&ctx.accounts.role.address_type, ctx.accounts.role.address) {
+        let wallet_roles = match ctx.accounts.wallet_roles.as_deref_mut() {
+            Some(wallet_roles) => wallet_roles,
+            None => return err!(Errors::MissingRegistryAccount),
+        };
+        wallet_roles.file_id = ctx.accounts.sol_gateway_file.id;
+        wallet_roles.wallet = wallet;
+        wallet_roles.bump = ctx.bumps.wallet_roles;
+        ctx.accounts.sol_gateway_file.add_child(&mut wallet_roles.payer, ctx.accounts.rent_payer.key());
+        wallet_roles_insert(
+            wallet_roles,
+            ctx.accounts.role.key(),
+            &ctx.accounts.rent_payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
 
-    emit!(RolesChanged {
-        time: utc_now(),
-        file_id: ctx.accounts.sol_gateway_file.id,
-    });
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+    }
+    emit_event!(ctx, RolesChanged::new(
+        now,
+        ChangeAction::Created,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.contributor.key(),
+    ));
     Ok(())
 }
@@ -0,0 +1,186 @@
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::state::file::{CacheUpdated, ChangeAction, File};
+use crate::state::registry::*;
+use crate::state::role::*;
+use crate::utils::{register, registry_page_index, seeds::role_seed, utc_now, wallet_roles_insert};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+
+/// Holders of a verified collection NFT claim the Wallet Role of a RoleTemplate, so the token
+/// and metadata accounts are only checked once (instead of on every access check).
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct ClaimRole<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = sol_gateway_file.deleted_at.is_none() @ Errors::FileDeleted,
+        constraint = sol_gateway_file.frozen_at.is_none() @ Errors::FileFrozen,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        seeds = [b"role_template".as_ref(), role_seed(&role_template.role, &AddressType::Collection, &Some(role_template.collection)).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = role_template.bump,
+    )]
+    pub role_template: Box<Account<'info, RoleTemplate>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = Role::MAX_SIZE,
+        seeds = [b"role".as_ref(), role_seed(&role_template.role, &AddressType::Wallet, &Some(signer.key())).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub role: Account<'info, Role>,
+
+    /** Ownership accounts */
+    #[account(
+        constraint = token.owner == signer.key() @ Errors::TokenNotOwned,
+        constraint = token.amount > 0 @ Errors::EmptyTokenBalance,
+    )]
+    pub token: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), token.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = RoleClaim::MAX_SIZE,
+        seeds = [b"claim".as_ref(), role_template.key().as_ref(), token.mint.as_ref()],
+        bump
+    )]
+    pub claim: Box<Account<'info, RoleClaim>>,
+
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page_index(registry.roles).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = WalletRoles::space(1),
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), signer.key().as_ref()],
+        bump
+    )]
+    pub wallet_roles: Box<Account<'info, WalletRoles>>,
+
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn claim_role(ctx: Context<ClaimRole>) -> Result<()> {
+    // The token must belong to the verified collection of the template
+    let template = &ctx.accounts.role_template;
+    if !matches!(&ctx.accounts.metadata.collection, Some(collection) if collection.verified && collection.key == template.collection) {
+        return err!(Errors::AddressMismatch);
+    }
+
+    // Each token claims the Role once per duration, whichever wallet holds it
+    let now = utc_now();
+    if ctx.accounts.claim.expires_at > now {
+        return err!(Errors::TokenAlreadyClaimed);
+    }
+    let claim = &mut ctx.accounts.claim;
+    claim.file_id = ctx.accounts.sol_gateway_file.id;
+    claim.template = ctx.accounts.role_template.key();
+    claim.mint = ctx.accounts.token.mint;
+    claim.wallet = ctx.accounts.signer.key();
+    claim.expires_at = now.saturating_add(template.duration);
+    claim.bump = ctx.bumps.claim;
+    ctx.accounts.sol_gateway_file.add_child(&mut claim.payer, ctx.accounts.signer.key());
+
+    // The Role of an expired claim still exists, so the holder renews it instead of creating it again
+    // (it is already counted, registered and listed on the wallet roles)
+    if ctx.accounts.role.file_id != Pubkey::default() {
+        let role = &mut ctx.accounts.role;
+        if !matches!(role.expires_at, Some(expires_at) if expires_at <= now) {
+            return err!(Errors::RoleNotExpired);
+        }
+        role.expires_at = Some(now.saturating_add(template.duration));
+        ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Updated, &*ctx.accounts.role)?;
+        if ctx.accounts.sol_gateway_file.cached {
+            ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+        }
+        emit_event!(ctx, RolesChanged::new(
+            now,
+            ChangeAction::Updated,
+            ctx.accounts.role.key(),
+            &ctx.accounts.role,
+            ctx.accounts.signer.key(),
+        ));
+        return Ok(());
+    }
+
+    let role = &mut ctx.accounts.role;
+    role.bump = ctx.bumps.role;
+    role.file_id = ctx.accounts.sol_gateway_file.id;
+    role.address = Some(ctx.accounts.signer.key());
+    role.role = template.role.clone();
+    role.address_type = AddressType::Wallet;
+    role.expires_at = Some(now.saturating_add(template.duration));
+    role.registered = true;
+    role.payer = ctx.accounts.signer.key();
+
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Created, &*ctx.accounts.role)?;
+    ctx.accounts.sol_gateway_file.add_role()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.signer.key(),
+        RegistryKind::Roles,
+        ctx.accounts.role.key(),
+    )?;
+    let wallet_roles = &mut ctx.accounts.wallet_roles;
+    wallet_roles.file_id = ctx.accounts.sol_gateway_file.id;
+    wallet_roles.wallet = ctx.accounts.signer.key();
+    wallet_roles.bump = ctx.bumps.wallet_roles;
+    ctx.accounts.sol_gateway_file.add_child(&mut wallet_roles.payer, ctx.accounts.signer.key());
+    wallet_roles_insert(
+        wallet_roles,
+        ctx.accounts.role.key(),
+        &ctx.accounts.signer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+    }
+    emit_event!(ctx, RolesChanged::new(
+        now,
+        ChangeAction::Created,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
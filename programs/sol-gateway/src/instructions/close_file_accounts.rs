@@ -0,0 +1,114 @@
+use crate::state::audit::AuditLog;
+use crate::state::config::ProgramConfig;
+use crate::state::file::*;
+use crate::state::metadata::{FileMetadata, MetadataSchema};
+use crate::state::permissions::PermissionRegistry;
+use crate::state::registry::{PolicyRegistry, RegistryPage, WalletRoles};
+use crate::state::request::RoleRequest;
+use crate::state::role::{Role, RoleClaim, RoleTemplate};
+use crate::state::rule::Rule;
+use crate::utils::{allowed_authority, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+/// Closes accounts of a deleted File, received as remaining accounts in pairs: the account and the
+/// one receiving its rent (the payer recorded on it). Anyone can close them, accounts without a
+/// recorded payer return the rent to the collector, which is the File authority unless the signer
+/// is the authority.
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct CloseFileAccounts<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = file.deleted_at.is_some() @ Errors::FileNotDeleted,
+    )]
+    pub file: Box<Account<'info, File>>,
+    /// CHECK: collector of the funds
+    #[account(
+        mut,
+        constraint = collector.key() == file.authority || allowed_authority(&signer.key(), &file.authority) @ Errors::Unauthorized,
+    )]
+    collector: AccountInfo<'info>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+}
+
+pub fn close_file_accounts<'info>(ctx: Context<'_, '_, 'info, 'info, CloseFileAccounts<'info>>) -> Result<()> {
+    let file = &mut ctx.accounts.file;
+    let mut closed: Vec<Pubkey> = Vec::new();
+    for pair in ctx.remaining_accounts.chunks(2) {
+        let (account, receiver) = match pair {
+            [account, receiver] => (account, receiver),
+            _ => return err!(Errors::InvalidTeardownAccount),
+        };
+        let payer = {
+            // Every account of a File stores its id right after the discriminator
+            let data = account.try_borrow_data()?;
+            if account.owner != &crate::ID || data.len() < 40 || data[8..40] != file.id.to_bytes() {
+                return err!(Errors::InvalidTeardownAccount);
+            }
+            let mut discriminator = [0u8; 8];
+            discriminator.copy_from_slice(&data[..8]);
+            let payer = match discriminator {
+                Role::DISCRIMINATOR => recorded::<Role, _>(&data, |a| a.payer),
+                Rule::DISCRIMINATOR => recorded::<Rule, _>(&data, |a| a.payer),
+                FileMetadata::DISCRIMINATOR => FileMetadata::from_typed(&data).map(|a| a.payer).unwrap_or_default(),
+                MetadataSchema::DISCRIMINATOR => recorded::<MetadataSchema, _>(&data, |a| a.payer),
+                AuditLog::DISCRIMINATOR => recorded::<AuditLog, _>(&data, |a| a.payer),
+                PermissionRegistry::DISCRIMINATOR => recorded::<PermissionRegistry, _>(&data, |a| a.payer),
+                PolicyRegistry::DISCRIMINATOR => recorded::<PolicyRegistry, _>(&data, |a| a.payer),
+                RegistryPage::DISCRIMINATOR => recorded::<RegistryPage, _>(&data, |a| a.payer),
+                WalletRoles::DISCRIMINATOR => recorded::<WalletRoles, _>(&data, |a| a.payer),
+                RoleTemplate::DISCRIMINATOR => recorded::<RoleTemplate, _>(&data, |a| a.payer),
+                RoleClaim::DISCRIMINATOR => recorded::<RoleClaim, _>(&data, |a| a.payer),
+                RoleRequest::DISCRIMINATOR => recorded::<RoleRequest, _>(&data, |a| a.payer),
+                _ => return err!(Errors::InvalidTeardownAccount),
+            };
+            // Only registered Roles and Rules are counted, other accounts are counted once their payer is recorded
+            match discriminator {
+                Role::DISCRIMINATOR if recorded::<Role, _>(&data, |a| a.registered) => file.remove_role(),
+                Rule::DISCRIMINATOR if recorded::<Rule, _>(&data, |a| a.registered) => file.remove_rule(),
+                Role::DISCRIMINATOR | Rule::DISCRIMINATOR => {}
+                _ if payer != Pubkey::default() => file.remove_child(),
+                _ => {}
+            }
+            payer
+        };
+        let expected_receiver = if payer == Pubkey::default() { ctx.accounts.collector.key() } else { payer };
+        if receiver.key() != expected_receiver {
+            return err!(Errors::InvalidRentReceiver);
+        }
+        // Same as Anchor's `close` constraint: drain the lamports and give the account back to the System Program
+        let lamports = account.lamports();
+        **account.try_borrow_mut_lamports()? = 0;
+        **receiver.try_borrow_mut_lamports()? += lamports;
+        account.assign(&anchor_lang::system_program::ID);
+        account.realloc(0, false)?;
+        closed.push(account.key());
+    }
+
+    emit_event!(ctx, FileAccountsClosed {
+        time: utc_now(),
+        file_id: ctx.accounts.file.id,
+        accounts: closed,
+        collector: ctx.accounts.collector.key(),
+        actor: ctx.accounts.signer.key(),
+    });
+    Ok(())
+}
+
+/// Field recorded on the account (e.g. its payer), default when the account was created before the
+/// field was introduced (or it is still stored with a legacy layout).
+fn recorded<T: AccountDeserialize, V: Default>(data: &[u8], field: fn(&T) -> V) -> V {
+    T::try_deserialize(&mut &data[..]).map_or(V::default(), |account| field(&account))
+}
@@ -0,0 +1,41 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::*;
+use crate::utils::{allowed_authority, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+/// Marks the migration of a legacy File as completed once every legacy Role and Rule went through
+/// "migrate_role" or "migrate_rule". Legacy accounts can't be listed on-chain, so the authority
+/// confirms it: from then on the File counters are trusted to close the File.
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct CompleteMigration<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = allowed_authority(&authority.key(), &sol_gateway_file.authority) @ Errors::Unauthorized,
+        constraint = sol_gateway_file.migrating @ Errors::FileNotMigrating,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+}
+
+pub fn complete_migration(ctx: Context<CompleteMigration>) -> Result<()> {
+    ctx.accounts.sol_gateway_file.migrating = false;
+
+    emit_event!(ctx, FileChanged {
+        time: utc_now(),
+        file_id: ctx.accounts.sol_gateway_file.id,
+        authority: ctx.accounts.sol_gateway_file.authority,
+    });
+    Ok(())
+}
@@ -1,13 +1,17 @@
 use crate::instructions::allowed::{allowed, AllowedRule};
 use crate::metadata_program;
-use crate::state::file::{File, Seed};
-use crate::state::role::{Role, RolesChanged};
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
+use crate::state::registry::*;
+use crate::state::role::{AddressType, Role, RolesChanged};
 use crate::state::rule::Namespaces;
 use crate::state::rule::Rule;
-use crate::utils::{roles::address_or_wildcard, utc_now};
+use crate::utils::{seeds::{role_seed, rule_seed}, unregister, utc_now, wallet_roles_remove};
+use crate::emit_event;
+use crate::Errors;
 use anchor_lang::prelude::*;
 use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 pub struct DeleteAssignedRole<'info> {
     #[account(mut)]
@@ -15,22 +19,23 @@ pub struct DeleteAssignedRole<'info> {
     #[account(
         mut,
         close = collector,
-        seeds = [role.role.as_ref(), address_or_wildcard(&role.address), sol_gateway_file.id.key().as_ref()],
+        seeds = [b"role".as_ref(), role_seed(&role.role, &role.address_type, &role.address).as_ref(), sol_gateway_file.id.key().as_ref()],
         bump = role.bump,
     )]
     pub role: Account<'info, Role>,
     #[account(
+        mut,
         seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
         bump = sol_gateway_file.bump,
     )]
     pub sol_gateway_file: Box<Account<'info, File>>,
     #[account(
-        seeds = [sol_gateway_role.role.as_ref(),  address_or_wildcard(&sol_gateway_role.address), sol_gateway_role.file_id.key().as_ref()],
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
         bump = sol_gateway_role.bump
     )]
     pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
     #[account(
-        seeds = [sol_gateway_rule.namespace.to_le_bytes().as_ref(), sol_gateway_rule.role.as_ref(), sol_gateway_rule.resource.as_ref(), sol_gateway_rule.permission.as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
         bump = sol_gateway_rule.bump,
     )]
     pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
@@ -50,6 +55,32 @@ pub struct DeleteAssignedRole<'info> {
         bump
     )]
     pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /** Registry accounts (pages are only required for registered accounts) */
+    /// CHECK: File registry, loaded on the handler as it may not exist yet
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump,
+    )]
+    pub registry: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page.page.to_le_bytes().as_ref()],
+        bump = registry_page.bump,
+    )]
+    pub registry_page: Option<Box<Account<'info, RegistryPage>>>,
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_last_page.page.to_le_bytes().as_ref()],
+        bump = registry_last_page.bump,
+    )]
+    pub registry_last_page: Option<Box<Account<'info, RegistryPage>>>,
+    #[account(
+        mut,
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), wallet_roles.wallet.as_ref()],
+        bump = wallet_roles.bump,
+    )]
+    pub wallet_roles: Option<Box<Account<'info, WalletRoles>>>, // Required for registered "Wallet" Roles
     /// CHECK: collector of the funds
     #[account(mut)]
     collector: AccountInfo<'info>,
@@ -62,6 +93,7 @@ pub fn delete_assigned_role(ctx: Context<DeleteAssignedRole>) -> Result<()> {
         &ctx.accounts.sol_gateway_file,
         &ctx.accounts.sol_gateway_role,
         &ctx.accounts.sol_gateway_rule,
+        &None,
         &ctx.accounts.sol_gateway_token,
         &ctx.accounts.sol_gateway_metadata,
         &mut ctx.accounts.sol_gateway_seed,
@@ -74,9 +106,38 @@ pub fn delete_assigned_role(ctx: Context<DeleteAssignedRole>) -> Result<()> {
         },
     )?;
 
-    emit!(RolesChanged {
-        time: utc_now(),
-        file_id: ctx.accounts.sol_gateway_file.id,
-    });
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Deleted, &*ctx.accounts.role)?;
+    // Roles created before the File registry are not listed (nor counted) until they are registered
+    if ctx.accounts.role.registered {
+        ctx.accounts.sol_gateway_file.remove_role();
+        unregister(
+            &ctx.accounts.registry,
+            RegistryKind::Roles,
+            ctx.accounts.registry_page.as_deref_mut().map(|page| &mut **page),
+            ctx.accounts.registry_last_page.as_deref_mut().map(|page| &mut **page),
+            &ctx.accounts.role.key(),
+        )?;
+        if let (AddressType::Wallet, Some(wallet)) = (&ctx.accounts.role.address_type, ctx.accounts.role.address) {
+            match ctx.accounts.wallet_roles.as_deref_mut() {
+                Some(wallet_roles) if wallet_roles.wallet == wallet => {
+                    wallet_roles_remove(wallet_roles, &ctx.accounts.role.key())?
+                }
+                Some(_) => return err!(Errors::InvalidRegistryPage),
+                None => return err!(Errors::MissingRegistryAccount),
+            }
+        }
+    }
+
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+    }
+    emit_event!(ctx, RolesChanged::new(
+        now,
+        ChangeAction::Deleted,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.signer.key(),
+    ));
     Ok(())
 }
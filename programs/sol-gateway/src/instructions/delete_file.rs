@@ -1,29 +1,108 @@
-use crate::state::file::{File, FileChanged};
-use crate::utils::file::allowed_authority;
-use crate::utils::utc_now;
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::state::file::*;
+use crate::state::role::Role;
+use crate::state::rule::{Namespaces, Rule};
+use crate::utils::{allowed_authority, role_seed, rule_seed, utc_now};
+use crate::emit_event;
 use crate::Errors;
 use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 pub struct DeleteFile<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub signer: Signer<'info>,
     #[account(
         mut,
-        close = collector,
-        constraint = allowed_authority(&authority.key(), &file.authority)  @ Errors::Unauthorized,
         seeds = [b"file".as_ref(), file.id.key().as_ref()], 
         bump = file.bump,
     )]
-    pub file: Account<'info, File>,
+    pub file: Box<Account<'info, File>>,
     /// CHECK: collector of the funds
     #[account(mut)]
     collector: AccountInfo<'info>,
+
+    /** Validation accounts (only required when signer is not the Authority) */
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-pub fn delete_file(ctx: Context<DeleteFile>) -> Result<()> {
-    emit!(FileChanged {
-        time: utc_now(),
+pub fn delete_file(ctx: Context<DeleteFile>, force: bool) -> Result<()> {
+    if ctx.accounts.file.deleted_at.is_none() {
+        // File authority is always allowed, other signers require a role on the DeleteFile namespace
+        allowed(
+            &ctx.accounts.signer,
+            &ctx.accounts.file,
+            &ctx.accounts.sol_gateway_role,
+            &ctx.accounts.sol_gateway_rule,
+            &None,
+            &ctx.accounts.sol_gateway_token,
+            &ctx.accounts.sol_gateway_metadata,
+            &mut ctx.accounts.sol_gateway_seed,
+            &ctx.accounts.system_program,
+            AllowedRule {
+                file_id: ctx.accounts.file.id,
+                namespace: Namespaces::DeleteFile as u8,
+                resource: "file".to_string(),
+                permission: "delete".to_string(),
+            },
+        )?;
+    } else if !allowed_authority(&ctx.accounts.signer.key(), &ctx.accounts.file.authority) {
+        // Only the authority closes a File once its teardown has started
+        return err!(Errors::Unauthorized);
+    }
+
+    // Roles, Rules and other File accounts would be orphaned (keeping their rent) once the File is
+    // closed, so forcing the deletion marks the File as deleted until "close_file_accounts" closes them.
+    // Migrating Files are never empty, as their legacy Roles and Rules may not be counted yet.
+    let file = &mut ctx.accounts.file;
+    let now = utc_now();
+    if !file.is_empty() {
+        if !force || file.deleted_at.is_some() {
+            return err!(Errors::FileNotEmpty);
+        }
+        file.deleted_at = Some(now);
+    } else {
+        file.close(ctx.accounts.collector.to_account_info())?;
+    }
+
+    emit_event!(ctx, FileChanged {
+        time: now,
         file_id: ctx.accounts.file.id,
         authority: ctx.accounts.file.authority,
     });
@@ -0,0 +1,53 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::{ChangeAction, File};
+use crate::state::role::*;
+use crate::utils::{allowed_authority, seeds::role_seed, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct DeleteRoleTemplate<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&authority.key(), &file.authority) @ Errors::Unauthorized,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        close = collector,
+        seeds = [b"role_template".as_ref(), role_seed(&role_template.role, &AddressType::Collection, &Some(role_template.collection)).as_ref(), file.id.key().as_ref()],
+        bump = role_template.bump,
+    )]
+    pub role_template: Account<'info, RoleTemplate>,
+    /// CHECK: collector of the funds
+    #[account(mut)]
+    collector: AccountInfo<'info>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+}
+
+/// Stops new claims, Roles already claimed are kept until they expire.
+pub fn delete_role_template(ctx: Context<DeleteRoleTemplate>) -> Result<()> {
+    ctx.accounts.file.remove_child();
+    emit_event!(ctx, RoleTemplateChanged {
+        time: utc_now(),
+        file_id: ctx.accounts.file.id,
+        action: ChangeAction::Deleted,
+        template: ctx.accounts.role_template.key(),
+        role_name: ctx.accounts.role_template.role.clone(),
+        collection: ctx.accounts.role_template.collection,
+        duration: ctx.accounts.role_template.duration,
+        actor: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
@@ -1,13 +1,17 @@
 use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 use crate::instructions::allowed::{allowed, AllowedRule};
-use crate::state::file::{File, Seed};
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
+use crate::state::permissions::PermissionRegistry;
+use crate::state::registry::*;
 use crate::state::role::Role;
 use crate::state::rule::*;
-use crate::utils::{utc_now, roles::address_or_wildcard};
+use crate::utils::{rule_permissions, unregister, utc_now, seeds::{rule_seed, role_seed}};
+use crate::emit_event;
 use anchor_lang::prelude::*;
 use crate::metadata_program;
 
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 pub struct DeleteRule<'info> {
     #[account(mut)]
@@ -15,27 +19,33 @@ pub struct DeleteRule<'info> {
     #[account(
         mut,
         close = collector,
-        seeds = [rule.namespace.to_le_bytes().as_ref(), rule.role.as_ref(), rule.resource.as_ref(), rule.permission.as_ref(), sol_gateway_file.id.key().as_ref()], 
+        seeds = [b"rule".as_ref(), rule_seed(rule.namespace, &rule.role, &rule.resource, &rule.permission).as_ref(), sol_gateway_file.id.key().as_ref()], 
         bump = rule.bump,
     )]
     pub rule: Account<'info, Rule>,
     #[account(
+        mut,
         seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
         bump = sol_gateway_file.bump,
     )]
     pub sol_gateway_file: Box<Account<'info, File>>,
     #[account(
-        seeds = [sol_gateway_role.role.as_ref(), address_or_wildcard(&sol_gateway_role.address), sol_gateway_role.file_id.key().as_ref()],
+        seeds = [b"permissions".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = permission_registry.bump,
+    )]
+    pub permission_registry: Option<Box<Account<'info, PermissionRegistry>>>,
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
         bump = sol_gateway_role.bump
     )]
     pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
     #[account(
-        seeds = [sol_gateway_rule.namespace.to_le_bytes().as_ref(), sol_gateway_rule.role.as_ref(), sol_gateway_rule.resource.as_ref(), sol_gateway_rule.permission.as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
         bump = sol_gateway_rule.bump,
     )]
     pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
     #[account(
-        seeds = [sol_gateway_rule2.namespace.to_le_bytes().as_ref(), sol_gateway_rule2.role.as_ref(), sol_gateway_rule2.resource.as_ref(), sol_gateway_rule2.permission.as_ref(), sol_gateway_rule2.file_id.key().as_ref()],
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule2.namespace, &sol_gateway_rule2.role, &sol_gateway_rule2.resource, &sol_gateway_rule2.permission).as_ref(), sol_gateway_rule2.file_id.key().as_ref()],
         bump = sol_gateway_rule2.bump,
     )]
     pub sol_gateway_rule2: Option<Box<Account<'info, Rule>>>,
@@ -55,6 +65,26 @@ pub struct DeleteRule<'info> {
         bump
     )]
     pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /** Registry accounts (pages are only required for registered accounts) */
+    /// CHECK: File registry, loaded on the handler as it may not exist yet
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump,
+    )]
+    pub registry: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Rules as u8], registry_page.page.to_le_bytes().as_ref()],
+        bump = registry_page.bump,
+    )]
+    pub registry_page: Option<Box<Account<'info, RegistryPage>>>,
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Rules as u8], registry_last_page.page.to_le_bytes().as_ref()],
+        bump = registry_last_page.bump,
+    )]
+    pub registry_last_page: Option<Box<Account<'info, RegistryPage>>>,
     /// CHECK: collector of the funds
     #[account(mut)]
     collector: AccountInfo<'info>,
@@ -70,6 +100,7 @@ pub fn delete_rule(
         &ctx.accounts.sol_gateway_file,
         &ctx.accounts.sol_gateway_role,
         &ctx.accounts.sol_gateway_rule,
+        &None,
         &ctx.accounts.sol_gateway_token,
         &ctx.accounts.sol_gateway_metadata,
         &mut ctx.accounts.sol_gateway_seed,
@@ -81,27 +112,55 @@ pub fn delete_rule(
             permission: ctx.accounts.rule.role.to_string(),
         },
     )?;
-    // // Checks if is allowed to delete a rule for this specific Resource and Permission.
-    allowed(
-        &ctx.accounts.signer,
-        &ctx.accounts.sol_gateway_file,
-        &ctx.accounts.sol_gateway_role,
-        &ctx.accounts.sol_gateway_rule2,
-        &ctx.accounts.sol_gateway_token,
-        &ctx.accounts.sol_gateway_metadata,
-        &mut None,
-        &ctx.accounts.system_program,
-        AllowedRule {
-            file_id: ctx.accounts.sol_gateway_file.id.key(),
-            namespace: Namespaces::DeleteRuleResourcePerm as u8,
-            resource: ctx.accounts.rule.resource.to_string(),
-            permission: ctx.accounts.rule.permission.to_string(),
-        },
+    // // Checks if is allowed to delete a rule for this specific Resource and Permission (each one of them on permission sets).
+    let permissions = rule_permissions(
+        &ctx.accounts.rule.permission,
+        ctx.accounts.rule.permissions,
+        ctx.accounts.permission_registry.as_deref().map(|registry| &**registry),
     )?;
+    for permission in permissions {
+        allowed(
+            &ctx.accounts.signer,
+            &ctx.accounts.sol_gateway_file,
+            &ctx.accounts.sol_gateway_role,
+            &ctx.accounts.sol_gateway_rule2,
+            &None,
+            &ctx.accounts.sol_gateway_token,
+            &ctx.accounts.sol_gateway_metadata,
+            &mut None,
+            &ctx.accounts.system_program,
+            AllowedRule {
+                file_id: ctx.accounts.sol_gateway_file.id.key(),
+                namespace: Namespaces::DeleteRuleResourcePerm as u8,
+                resource: ctx.accounts.rule.resource.to_string(),
+                permission,
+            },
+        )?;
+    }
+
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Deleted, &*ctx.accounts.rule)?;
+    // Rules created before the File registry are not listed (nor counted) until they are registered
+    if ctx.accounts.rule.registered {
+        ctx.accounts.sol_gateway_file.remove_rule();
+        unregister(
+            &ctx.accounts.registry,
+            RegistryKind::Rules,
+            ctx.accounts.registry_page.as_deref_mut().map(|page| &mut **page),
+            ctx.accounts.registry_last_page.as_deref_mut().map(|page| &mut **page),
+            &ctx.accounts.rule.key(),
+        )?;
+    }
 
-    emit!(RulesChanged {
-        time: utc_now(),
-        file_id: ctx.accounts.sol_gateway_file.id,
-    });
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Rules, now);
+    }
+    emit_event!(ctx, RulesChanged::new(
+        now,
+        ChangeAction::Deleted,
+        ctx.accounts.rule.key(),
+        &ctx.accounts.rule,
+        ctx.accounts.signer.key(),
+    ));
     Ok(())
 }
@@ -0,0 +1,41 @@
+use crate::state::file::*;
+use crate::utils::{allowed_authority, allowed_recovery, utc_now};
+use crate::Errors;
+use crate::MAX_FROZEN_NAMESPACES;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct FreezeFile<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&signer.key(), &file.authority) || allowed_recovery(&signer.key(), &file) @ Errors::Unauthorized,
+        constraint = file.deleted_at.is_none() @ Errors::FileDeleted,
+    )]
+    pub file: Box<Account<'info, File>>,
+}
+
+/// Denies every access check but the authority's, except for the given namespaces.
+/// Freezing an already frozen File replaces the allowed namespaces.
+pub fn freeze_file(ctx: Context<FreezeFile>, namespaces: Vec<u8>) -> Result<()> {
+    if namespaces.len() > MAX_FROZEN_NAMESPACES {
+        return err!(Errors::TooManyFrozenNamespaces);
+    }
+    let now = utc_now();
+    let file = &mut ctx.accounts.file;
+    file.frozen_at = Some(now);
+    file.frozen_namespaces = namespaces.clone();
+
+    emit_event!(ctx, FileFrozen {
+        time: now,
+        file_id: ctx.accounts.file.id,
+        frozen: true,
+        namespaces,
+        actor: ctx.accounts.signer.key(),
+    });
+    Ok(())
+}
@@ -1,8 +1,11 @@
 use crate::utils::utc_now;
-use crate::{state::file::*, utils::validate_string_len};
-use crate::{Errors, FileMetadata};
+use crate::{state::file::*, utils::{validate_metadata, validate_schema, validate_schema_fields, validate_string_len}};
+use crate::{Errors, FileMetadata, MetadataSchema};
+use crate::emit_event;
+use crate::state::config::ProgramConfig;
 use anchor_lang::prelude::*;
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 #[instruction(file_data: FileData)]
 pub struct InitializeFiles<'info> {
@@ -21,15 +24,31 @@ pub struct InitializeFiles<'info> {
     #[account(
         init_if_needed,
         payer = rent_payer,
-        space = FileMetadata::MAX_SIZE,
+        space = FileMetadata::space(file_data.metadata.as_deref().unwrap_or(&[])),
         seeds = [b"metadata".as_ref(), file_data.id.key().as_ref()],
         bump,
     )]
     pub file_metadata: Option<Account<'info, FileMetadata>>,
 
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        space = MetadataSchema::MAX_SIZE,
+        seeds = [b"schema".as_ref(), file_data.id.key().as_ref()],
+        bump,
+    )]
+    pub metadata_schema: Option<Box<Account<'info, MetadataSchema>>>,
+
     #[account(mut)]
     pub rent_payer: Signer<'info>,
 
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -49,12 +68,35 @@ pub fn initialize_files(ctx: Context<InitializeFiles>, file_data: FileData) -> R
     file.expires_at = file_data.expires_at;
     file.bump = ctx.bumps.file;
 
+    // Initialize metadata schema if provided
+    match (file_data.schema, &mut ctx.accounts.metadata_schema) {
+        (Some(fields), Some(metadata_schema)) => {
+            validate_schema_fields(&fields)?;
+            validate_schema(file_data.metadata.as_deref().unwrap_or(&[]), &fields)?;
+            metadata_schema.file_id = file.id;
+            metadata_schema.fields = fields;
+            metadata_schema.bump = ctx.bumps.metadata_schema;
+            file.add_child(&mut metadata_schema.payer, ctx.accounts.rent_payer.key());
+        }
+        (Some(_), None) => {
+            return err!(Errors::MetadataSchemaAccountNotFound);
+        }
+        (None, Some(_)) => {
+            return err!(Errors::UnexpectedMetadataSchemaAccount);
+        }
+        (None, None) => {
+            // No schema provided and no schema account
+        }
+    }
+
     // Initialize metadata if provided
     match (file_data.metadata, &mut ctx.accounts.file_metadata) {
         (Some(metadata), Some(file_metadata)) => {
+            validate_metadata(&metadata)?;
             file_metadata.file_id = file.id;
             file_metadata.metadata = metadata;
             file_metadata.bump = ctx.bumps.file_metadata;
+            file.add_child(&mut file_metadata.payer, ctx.accounts.rent_payer.key());
         }
         (Some(_), None) => {
             return err!(Errors::FileMetadataAccountNotFound);
@@ -67,8 +109,8 @@ pub fn initialize_files(ctx: Context<InitializeFiles>, file_data: FileData) -> R
         }
     }
 
-    emit!(FileChanged {
-        time: file.rules_updated_at,
+    emit_event!(ctx, FileChanged {
+        time: ctx.accounts.file.rules_updated_at,
         file_id: ctx.accounts.file.id,
         authority: ctx.accounts.file.authority,
     });
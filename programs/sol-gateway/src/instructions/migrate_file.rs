@@ -0,0 +1,48 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::*;
+use crate::utils::{allowed_authority, grow_account};
+use crate::Errors;
+use anchor_lang::prelude::*;
+
+/// Rewrites a File stored with the legacy layout (without policy hash and counters), keeping its address.
+/// The File starts with an empty policy hash and no Roles or Rules counted, its legacy Roles and Rules
+/// are folded in one by one through "migrate_role" and "migrate_rule" until "complete_migration".
+#[derive(Accounts)]
+pub struct MigrateFile<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), legacy_file.id.key().as_ref()],
+        bump = legacy_file.bump,
+        constraint = allowed_authority(&authority.key(), &legacy_file.authority) @ Errors::Unauthorized,
+    )]
+    pub legacy_file: Account<'info, LegacyFile>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_file(ctx: Context<MigrateFile>) -> Result<()> {
+    let info = ctx.accounts.legacy_file.to_account_info();
+    if info.data_len() >= File::MAX_SIZE {
+        return err!(Errors::FileAlreadyMigrated);
+    }
+    let file = ctx.accounts.legacy_file.to_file();
+
+    // Rent for the new size is paid by the authority
+    grow_account(
+        &info,
+        File::MAX_SIZE,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    let mut data = info.try_borrow_mut_data()?;
+    file.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::File;
+use crate::state::metadata::*;
+use crate::utils::allowed_authority;
+use crate::Errors;
+use anchor_lang::prelude::*;
+
+/// Rewrites a FileMetadata account stored with the legacy (string only) values layout
+/// into typed values, keeping its address. Accounts already typed are rejected, they were counted
+/// on the File when created (or migrated).
+#[derive(Accounts)]
+pub struct MigrateMetadata<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&authority.key(), &file.authority) @ Errors::Unauthorized,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        seeds = [b"metadata".as_ref(), file.id.key().as_ref()],
+        bump = legacy_file_metadata.bump,
+        constraint = !FileMetadata::is_typed(&legacy_file_metadata.to_account_info()) @ Errors::MetadataAlreadyMigrated,
+        realloc = FileMetadata::space(&legacy_file_metadata.typed_metadata()),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub legacy_file_metadata: Account<'info, LegacyFileMetadata>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_metadata(ctx: Context<MigrateMetadata>) -> Result<()> {
+    let legacy_file_metadata = &ctx.accounts.legacy_file_metadata;
+    let mut file_metadata = FileMetadata {
+        file_id: legacy_file_metadata.file_id,
+        metadata: legacy_file_metadata.typed_metadata(),
+        bump: legacy_file_metadata.bump,
+        payer: Pubkey::default(),
+    };
+    // Legacy metadata was never counted on the File, its original payer is unknown
+    ctx.accounts.file.add_child(&mut file_metadata.payer, ctx.accounts.authority.key());
+    let info = legacy_file_metadata.to_account_info();
+    let mut data = info.try_borrow_mut_data()?;
+    file_metadata.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
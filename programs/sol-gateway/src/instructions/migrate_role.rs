@@ -0,0 +1,144 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::{ChangeAction, File};
+use crate::state::registry::*;
+use crate::state::role::*;
+use crate::utils::{allowed_authority, register, registry_page_index, roles::address_or_wildcard, seeds::{name_seed, role_seed}, utc_now, wallet_roles_insert};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+/// Moves a Role derived with the legacy seeds [role, address_or_wildcard, file_id]
+/// to its collision-free address [b"role", role_seed(..), file_id].
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct MigrateRole<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = allowed_authority(&authority.key(), &sol_gateway_file.authority) @ Errors::Unauthorized,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        close = collector,
+        seeds = [name_seed(&legacy_role.role).as_ref(), address_or_wildcard(&legacy_role.address), sol_gateway_file.id.key().as_ref()],
+        bump = legacy_role.bump,
+    )]
+    pub legacy_role: Account<'info, LegacyRole>,
+    #[account(
+        init,
+        payer = authority,
+        space = Role::MAX_SIZE,
+        seeds = [b"role".as_ref(), role_seed(&legacy_role.role, &legacy_role.address_type, &legacy_role.address).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub role: Account<'info, Role>,
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page_index(registry.roles).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = WalletRoles::space(1),
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), legacy_role.address.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub wallet_roles: Option<Box<Account<'info, WalletRoles>>>, // Required for "Wallet" Roles
+    /// CHECK: collector of the funds, legacy Roles don't record their payer so their rent goes to the File authority
+    #[account(
+        mut,
+        constraint = collector.key() == sol_gateway_file.authority @ Errors::Unauthorized,
+    )]
+    collector: AccountInfo<'info>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_role(ctx: Context<MigrateRole>) -> Result<()> {
+    let legacy_role = &ctx.accounts.legacy_role;
+    let role = &mut ctx.accounts.role;
+    role.bump = ctx.bumps.role;
+    role.file_id = legacy_role.file_id;
+    role.address = legacy_role.address;
+    role.role = legacy_role.role.clone();
+    role.address_type = legacy_role.address_type.clone();
+    role.expires_at = legacy_role.expires_at;
+    role.registered = true;
+    role.payer = ctx.accounts.authority.key();
+
+    // Both events below are recorded, the legacy Role holds the same policy fields as the new one.
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Deleted, &*ctx.accounts.role)?;
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Created, &*ctx.accounts.role)?;
+    ctx.accounts.sol_gateway_file.add_role()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.authority.key(),
+        RegistryKind::Roles,
+        ctx.accounts.role.key(),
+    )?;
+    if let (AddressType::Wallet, Some(wallet)) = (&ctx.accounts.role.address_type, ctx.accounts.role.address) {
+        let wallet_roles = match ctx.accounts.wallet_roles.as_deref_mut() {
+            Some(wallet_roles) => wallet_roles,
+            None => return err!(Errors::MissingRegistryAccount),
+        };
+        wallet_roles.file_id = ctx.accounts.sol_gateway_file.id;
+        wallet_roles.wallet = wallet;
+        wallet_roles.bump = ctx.bumps.wallet_roles;
+        ctx.accounts.sol_gateway_file.add_child(&mut wallet_roles.payer, ctx.accounts.authority.key());
+        wallet_roles_insert(
+            wallet_roles,
+            ctx.accounts.role.key(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
+
+    // Legacy Role is closed and replaced by the new one.
+    let legacy_role = &ctx.accounts.legacy_role;
+    emit_event!(ctx, RolesChanged {
+        time: utc_now(),
+        file_id: legacy_role.file_id,
+        action: ChangeAction::Deleted,
+        role: legacy_role.key(),
+        role_name: legacy_role.role.clone(),
+        address: legacy_role.address,
+        address_type: legacy_role.address_type.clone(),
+        expires_at: legacy_role.expires_at,
+        actor: ctx.accounts.authority.key(),
+    });
+    emit_event!(ctx, RolesChanged::new(
+        utc_now(),
+        ChangeAction::Created,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.authority.key(),
+    ));
+    Ok(())
+}
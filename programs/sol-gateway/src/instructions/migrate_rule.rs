@@ -0,0 +1,124 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::{ChangeAction, File};
+use crate::state::registry::*;
+use crate::state::rule::*;
+use crate::utils::{allowed_authority, register, registry_page_index, seeds::{name_seed, rule_seed}, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+/// Moves a Rule derived with the legacy seeds [namespace, role, resource, permission, file_id]
+/// to its collision-free address [b"rule", rule_seed(..), file_id].
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct MigrateRule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = allowed_authority(&authority.key(), &sol_gateway_file.authority) @ Errors::Unauthorized,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        close = collector,
+        seeds = [legacy_rule.namespace.to_le_bytes().as_ref(), name_seed(&legacy_rule.role).as_ref(), name_seed(&legacy_rule.resource).as_ref(), name_seed(&legacy_rule.permission).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = legacy_rule.bump,
+    )]
+    pub legacy_rule: Account<'info, LegacyRule>,
+    #[account(
+        init,
+        payer = authority,
+        space = Rule::MAX_SIZE,
+        seeds = [b"rule".as_ref(), rule_seed(legacy_rule.namespace, &legacy_rule.role, &legacy_rule.resource, &legacy_rule.permission).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub rule: Account<'info, Rule>,
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Rules as u8], registry_page_index(registry.rules).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    /// CHECK: collector of the funds, legacy Rules don't record their payer so their rent goes to the File authority
+    #[account(
+        mut,
+        constraint = collector.key() == sol_gateway_file.authority @ Errors::Unauthorized,
+    )]
+    collector: AccountInfo<'info>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_rule(ctx: Context<MigrateRule>) -> Result<()> {
+    let legacy_rule = &ctx.accounts.legacy_rule;
+    let rule = &mut ctx.accounts.rule;
+    rule.bump = ctx.bumps.rule;
+    rule.file_id = legacy_rule.file_id;
+    rule.namespace = legacy_rule.namespace;
+    rule.role = legacy_rule.role.clone();
+    rule.resource = legacy_rule.resource.clone();
+    rule.permission = legacy_rule.permission.clone();
+    rule.permissions = 0;
+    rule.expires_at = legacy_rule.expires_at;
+    rule.registered = true;
+    rule.payer = ctx.accounts.authority.key();
+
+    // Both events below are recorded, the legacy Rule holds the same policy fields as the new one.
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Deleted, &*ctx.accounts.rule)?;
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Created, &*ctx.accounts.rule)?;
+    ctx.accounts.sol_gateway_file.add_rule()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.authority.key(),
+        RegistryKind::Rules,
+        ctx.accounts.rule.key(),
+    )?;
+
+    // Legacy Rule is closed and replaced by the new one.
+    let legacy_rule = &ctx.accounts.legacy_rule;
+    emit_event!(ctx, RulesChanged {
+        time: utc_now(),
+        file_id: legacy_rule.file_id,
+        action: ChangeAction::Deleted,
+        rule: legacy_rule.key(),
+        namespace: legacy_rule.namespace,
+        role: legacy_rule.role.clone(),
+        resource: legacy_rule.resource.clone(),
+        permission: legacy_rule.permission.clone(),
+        permissions: 0,
+        expires_at: legacy_rule.expires_at,
+        actor: ctx.accounts.authority.key(),
+    });
+    emit_event!(ctx, RulesChanged::new(
+        utc_now(),
+        ChangeAction::Created,
+        ctx.accounts.rule.key(),
+        &ctx.accounts.rule,
+        ctx.accounts.authority.key(),
+    ));
+    Ok(())
+}
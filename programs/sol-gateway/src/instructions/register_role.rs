@@ -0,0 +1,107 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::File;
+use crate::state::registry::*;
+use crate::state::role::*;
+use crate::utils::{allowed_authority, grow_account, register, registry_page_index, seeds::role_seed, wallet_roles_insert};
+use crate::Errors;
+use anchor_lang::prelude::*;
+
+/// Lists a Role created before the File had a registry on it (and on its WalletRoles), so it can be
+/// deleted or renounced once the File registry exists. The Role is loaded with its previous layout.
+#[derive(Accounts)]
+pub struct RegisterRole<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = allowed_authority(&authority.key(), &sol_gateway_file.authority) @ Errors::Unauthorized,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        seeds = [b"role".as_ref(), role_seed(&role.role, &role.address_type, &role.address).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = role.bump,
+        constraint = role.to_account_info().data_len() < Role::MAX_SIZE @ Errors::AlreadyRegistered, // Registered Roles use the current layout
+    )]
+    pub role: Account<'info, LegacyRole>,
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page_index(registry.roles).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = WalletRoles::space(1),
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), role.address.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub wallet_roles: Option<Box<Account<'info, WalletRoles>>>, // Required for "Wallet" Roles
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn register_role(ctx: Context<RegisterRole>) -> Result<()> {
+    // Registration is bookkeeping only, the policy hash and events are not changed.
+    let role = ctx.accounts.role.to_role();
+    // Roles created before the registry were not counted either
+    ctx.accounts.sol_gateway_file.add_role()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.authority.key(),
+        RegistryKind::Roles,
+        ctx.accounts.role.key(),
+    )?;
+    if let (AddressType::Wallet, Some(wallet)) = (&ctx.accounts.role.address_type, ctx.accounts.role.address) {
+        let wallet_roles = match ctx.accounts.wallet_roles.as_deref_mut() {
+            Some(wallet_roles) => wallet_roles,
+            None => return err!(Errors::MissingRegistryAccount),
+        };
+        wallet_roles.file_id = ctx.accounts.sol_gateway_file.id;
+        wallet_roles.wallet = wallet;
+        wallet_roles.bump = ctx.bumps.wallet_roles;
+        ctx.accounts.sol_gateway_file.add_child(&mut wallet_roles.payer, ctx.accounts.authority.key());
+        wallet_roles_insert(
+            wallet_roles,
+            ctx.accounts.role.key(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
+
+    // The Role is rewritten with the current layout, the authority paying the rent of the new size.
+    let info = ctx.accounts.role.to_account_info();
+    grow_account(
+        &info,
+        Role::MAX_SIZE,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    let mut data = info.try_borrow_mut_data()?;
+    role.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::File;
+use crate::state::registry::*;
+use crate::state::rule::*;
+use crate::utils::{allowed_authority, grow_account, register, registry_page_index, seeds::rule_seed};
+use crate::Errors;
+use anchor_lang::prelude::*;
+
+/// Lists a Rule created before the File had a registry on it, so it can be deleted once the
+/// File registry exists. The Rule is loaded with its previous layout.
+#[derive(Accounts)]
+pub struct RegisterRule<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = allowed_authority(&authority.key(), &sol_gateway_file.authority) @ Errors::Unauthorized,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        seeds = [b"rule".as_ref(), rule_seed(rule.namespace, &rule.role, &rule.resource, &rule.permission).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = rule.bump,
+        constraint = rule.to_account_info().data_len() < Rule::MAX_SIZE @ Errors::AlreadyRegistered, // Registered Rules use the current layout
+    )]
+    pub rule: Account<'info, UnregisteredRule>,
+    /** Registry accounts */
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PolicyRegistry::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump
+    )]
+    pub registry: Box<Account<'info, PolicyRegistry>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RegistryPage::MAX_SIZE,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Rules as u8], registry_page_index(registry.rules).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn register_rule(ctx: Context<RegisterRule>) -> Result<()> {
+    // Registration is bookkeeping only, the policy hash and events are not changed.
+    let rule = ctx.accounts.rule.to_rule();
+    // Rules created before the registry were not counted either
+    ctx.accounts.sol_gateway_file.add_rule()?;
+    register(
+        &mut ctx.accounts.registry,
+        ctx.bumps.registry,
+        &mut ctx.accounts.registry_page,
+        ctx.bumps.registry_page,
+        &mut ctx.accounts.sol_gateway_file,
+        ctx.accounts.authority.key(),
+        RegistryKind::Rules,
+        ctx.accounts.rule.key(),
+    )?;
+
+    // The Rule is rewritten with the current layout, the authority paying the rent of the new size.
+    let info = ctx.accounts.rule.to_account_info();
+    grow_account(
+        &info,
+        Rule::MAX_SIZE,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    let mut data = info.try_borrow_mut_data()?;
+    rule.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::state::file::{File, Seed};
+use crate::state::request::*;
+use crate::state::role::{AddressType, Role};
+use crate::state::rule::{Namespaces, Rule};
+use crate::utils::{seeds::{role_seed, rule_seed}, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct RejectRequest<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"request".as_ref(), role_seed(&request.role, &AddressType::Wallet, &Some(request.wallet)).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = request.bump,
+    )]
+    pub request: Account<'info, RoleRequest>,
+    /// CHECK: Wallet that requested the Role (receives the rent of the request)
+    #[account(
+        mut,
+        constraint = requester.key() == request.wallet @ Errors::Unauthorized,
+    )]
+    pub requester: AccountInfo<'info>,
+
+    /** Validation accounts (only required when signer is neither the Authority nor the requester) */
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Closes the request without assigning the Role (the requesting wallet can also cancel it).
+pub fn reject_request(ctx: Context<RejectRequest>) -> Result<()> {
+    let cancelled = ctx.accounts.signer.key() == ctx.accounts.request.wallet;
+    // File authority is always allowed, other signers require a role on the ReviewRequest namespace
+    if !cancelled {
+        allowed(
+            &ctx.accounts.signer,
+            &ctx.accounts.sol_gateway_file,
+            &ctx.accounts.sol_gateway_role,
+            &ctx.accounts.sol_gateway_rule,
+            &None,
+            &ctx.accounts.sol_gateway_token,
+            &ctx.accounts.sol_gateway_metadata,
+            &mut ctx.accounts.sol_gateway_seed,
+            &ctx.accounts.system_program,
+            AllowedRule {
+                file_id: ctx.accounts.sol_gateway_file.id,
+                namespace: Namespaces::ReviewRequest as u8,
+                resource: "request".to_string(),
+                permission: ctx.accounts.request.role.clone(),
+            },
+        )?;
+    }
+
+    ctx.accounts.sol_gateway_file.remove_child();
+    emit_event!(ctx, RoleRequestChanged::new(
+        utc_now(),
+        if cancelled { RequestAction::Cancelled } else { RequestAction::Rejected },
+        ctx.accounts.request.key(),
+        &ctx.accounts.request,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::state::role::Role;
+use crate::state::rule::{Namespaces, Rule};
+use crate::{
+    state::file::*,
+    utils::{resize_account, role_seed, rule_seed, validate_schema},
+    Errors, FileMetadata, MetadataSchema, MetadataUpdated,
+};
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+#[instruction(key: String)]
+pub struct RemoveMetadataEntry<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        mut,
+        seeds = [b"metadata".as_ref(), file.id.key().as_ref()],
+        bump = file_metadata.bump,
+        constraint = FileMetadata::is_typed(&file_metadata.to_account_info()) @ Errors::MetadataNotMigrated,
+    )]
+    pub file_metadata: Account<'info, FileMetadata>,
+    /// CHECK: Schema of the File metadata (when initialized)
+    #[account(
+        seeds = [b"schema".as_ref(), file.id.key().as_ref()],
+        bump,
+    )]
+    pub metadata_schema: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub rent_payer: Signer<'info>, // Pays the extra rent when metadata grows
+    /// CHECK: Receives the rent refunded when metadata shrinks, the account that paid for the metadata
+    #[account(
+        mut,
+        constraint = rent_receiver.key() == file_metadata.payer @ Errors::InvalidRentReceiver,
+    )]
+    pub rent_receiver: UncheckedAccount<'info>,
+
+    /** Validation accounts (only required when signer is not the Authority) */
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Removes the metadata entry of the provided key.
+pub fn remove_metadata_entry(ctx: Context<RemoveMetadataEntry>, key: String) -> Result<()> {
+    // File authority is always allowed, other signers require a role on the UpdateMetadata namespace
+    allowed(
+        &ctx.accounts.signer,
+        &ctx.accounts.file,
+        &ctx.accounts.sol_gateway_role,
+        &ctx.accounts.sol_gateway_rule,
+        &None,
+        &ctx.accounts.sol_gateway_token,
+        &ctx.accounts.sol_gateway_metadata,
+        &mut ctx.accounts.sol_gateway_seed,
+        &ctx.accounts.system_program,
+        AllowedRule {
+            file_id: ctx.accounts.file.id,
+            namespace: Namespaces::UpdateMetadata as u8,
+            resource: "metadata".to_string(),
+            permission: key.clone(),
+        },
+    )?;
+
+    let file = &ctx.accounts.file;
+    let file_metadata = &mut ctx.accounts.file_metadata;
+    let index = file_metadata
+        .metadata
+        .iter()
+        .position(|m| m.key == key)
+        .ok_or(Errors::MetadataKeyNotFound)?;
+    file_metadata.metadata.remove(index);
+    if let Some(schema) = MetadataSchema::load(&ctx.accounts.metadata_schema)? {
+        validate_schema(&file_metadata.metadata, &schema.fields)?;
+    }
+    // Extra rent is paid by the rent payer, the rent no longer required goes back to the metadata payer
+    let space = FileMetadata::space(&file_metadata.metadata);
+    resize_account(
+        &file_metadata.to_account_info(),
+        space,
+        &ctx.accounts.rent_payer.to_account_info(),
+        &ctx.accounts.rent_receiver.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    emit_event!(ctx, MetadataUpdated {
+        time: Clock::get()?.unix_timestamp,
+        file_id: file.id,
+        authority: ctx.accounts.file.authority,
+        keys: vec![key],
+    });
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::state::file::{CacheUpdated, ChangeAction, File};
+use crate::state::registry::*;
+use crate::state::role::{AddressType, Role, RolesChanged};
+use crate::utils::{seeds::role_seed, unregister, utc_now, wallet_roles_remove};
+use crate::emit_event;
+use crate::Errors;
+use anchor_lang::prelude::*;
+
+/// The wallet holding a Role closes it, no rule is required. The rent is returned to the account
+/// that paid for the Role.
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct RenounceRole<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        close = collector,
+        seeds = [b"role".as_ref(), role_seed(&role.role, &role.address_type, &role.address).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = role.bump,
+        constraint = matches!(role.address_type, AddressType::Wallet) && role.address == Some(signer.key()) @ Errors::NotRoleHolder,
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    /** Registry accounts (pages are only required for registered accounts) */
+    /// CHECK: File registry, loaded on the handler as it may not exist yet
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump,
+    )]
+    pub registry: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_page.page.to_le_bytes().as_ref()],
+        bump = registry_page.bump,
+    )]
+    pub registry_page: Option<Box<Account<'info, RegistryPage>>>,
+    #[account(
+        mut,
+        seeds = [b"registry".as_ref(), sol_gateway_file.id.key().as_ref(), &[RegistryKind::Roles as u8], registry_last_page.page.to_le_bytes().as_ref()],
+        bump = registry_last_page.bump,
+    )]
+    pub registry_last_page: Option<Box<Account<'info, RegistryPage>>>,
+    #[account(
+        mut,
+        seeds = [b"wallet_roles".as_ref(), sol_gateway_file.id.key().as_ref(), wallet_roles.wallet.as_ref()],
+        bump = wallet_roles.bump,
+    )]
+    pub wallet_roles: Option<Box<Account<'info, WalletRoles>>>, // Required for registered Roles
+    /// CHECK: collector of the funds
+    #[account(
+        mut,
+        constraint = collector.key() == role.rent_collector(&sol_gateway_file.authority) @ Errors::Unauthorized,
+    )]
+    collector: AccountInfo<'info>,
+}
+
+pub fn renounce_role(ctx: Context<RenounceRole>) -> Result<()> {
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Deleted, &*ctx.accounts.role)?;
+    // Roles created before the File registry are not listed (nor counted) until they are registered
+    if ctx.accounts.role.registered {
+        ctx.accounts.sol_gateway_file.remove_role();
+        unregister(
+            &ctx.accounts.registry,
+            RegistryKind::Roles,
+            ctx.accounts.registry_page.as_deref_mut().map(|page| &mut **page),
+            ctx.accounts.registry_last_page.as_deref_mut().map(|page| &mut **page),
+            &ctx.accounts.role.key(),
+        )?;
+        // Only Wallet Roles can be renounced, so registered ones are listed on the holder's WalletRoles
+        match ctx.accounts.wallet_roles.as_deref_mut() {
+            Some(wallet_roles) if wallet_roles.wallet == ctx.accounts.signer.key() => {
+                wallet_roles_remove(wallet_roles, &ctx.accounts.role.key())?
+            }
+            Some(_) => return err!(Errors::InvalidRegistryPage),
+            None => return err!(Errors::MissingRegistryAccount),
+        }
+    }
+
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+    }
+    emit_event!(ctx, RolesChanged::new(
+        now,
+        ChangeAction::Deleted,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::File;
+use crate::state::request::*;
+use crate::state::role::AddressType;
+use crate::utils::{seeds::role_seed, utc_now, valid_rule};
+use crate::Errors::{self, InvalidRole};
+use crate::MAX_JUSTIFICATION_LENGTH;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+#[instruction(request_data: RoleRequestData)]
+pub struct RequestRole<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+        constraint = sol_gateway_file.deleted_at.is_none() @ Errors::FileDeleted,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        init,
+        payer = signer,
+        space = RoleRequest::MAX_SIZE,
+        seeds = [b"request".as_ref(), role_seed(&request_data.role, &AddressType::Wallet, &Some(signer.key())).as_ref(), sol_gateway_file.id.key().as_ref()],
+        constraint = valid_rule(&request_data.role, false) @ InvalidRole,
+        bump
+    )]
+    pub request: Account<'info, RoleRequest>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates a pending request of a Wallet Role for the signer, reviewed by roles on the ReviewRequest namespace.
+pub fn request_role(ctx: Context<RequestRole>, request_data: RoleRequestData) -> Result<()> {
+    if matches!(&request_data.justification, Some(justification) if justification.len() > MAX_JUSTIFICATION_LENGTH) {
+        return err!(Errors::JustificationTooLong);
+    }
+    let now = utc_now();
+    let request = &mut ctx.accounts.request;
+    request.file_id = ctx.accounts.sol_gateway_file.id;
+    request.wallet = ctx.accounts.signer.key();
+    request.role = request_data.role;
+    request.justification = request_data.justification;
+    request.expires_at = request_data.expires_at;
+    request.created_at = now;
+    request.bump = ctx.bumps.request;
+    ctx.accounts.sol_gateway_file.add_child(&mut request.payer, ctx.accounts.signer.key());
+
+    emit_event!(ctx, RoleRequestChanged::new(
+        now,
+        RequestAction::Created,
+        ctx.accounts.request.key(),
+        &ctx.accounts.request,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use crate::{
+    state::file::*,
+    utils::{allowed_authority, validate_schema_fields},
+    Errors, MetadataSchema, MetadataSchemaData, MetadataSchemaUpdated,
+};
+use crate::emit_event;
+use crate::state::config::ProgramConfig;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct SetMetadataSchema<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&signer.key(), &file.authority) @ Errors::UnauthorizedMetadataUpdate,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = MetadataSchema::MAX_SIZE,
+        seeds = [b"schema".as_ref(), file.id.key().as_ref()],
+        bump
+    )]
+    pub metadata_schema: Box<Account<'info, MetadataSchema>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Replaces the schema enforced on the File metadata (empty fields remove any restriction).
+pub fn set_metadata_schema(
+    ctx: Context<SetMetadataSchema>,
+    schema_data: MetadataSchemaData,
+) -> Result<()> {
+    validate_schema_fields(&schema_data.fields)?;
+    let metadata_schema = &mut ctx.accounts.metadata_schema;
+    metadata_schema.file_id = ctx.accounts.file.id;
+    metadata_schema.fields = schema_data.fields;
+    metadata_schema.bump = ctx.bumps.metadata_schema;
+    ctx.accounts.file.add_child(&mut metadata_schema.payer, ctx.accounts.signer.key());
+
+    emit_event!(ctx, MetadataSchemaUpdated {
+        time: Clock::get()?.unix_timestamp,
+        file_id: ctx.accounts.file.id,
+        authority: ctx.accounts.file.authority,
+        fields: ctx.accounts.metadata_schema.fields.clone(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::state::config::*;
+use crate::utils::utc_now;
+use crate::Errors;
+use crate::PROGRAM_AUTHORITY;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == PROGRAM_AUTHORITY @ Errors::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ProgramConfig::MAX_SIZE,
+        seeds = [b"config".as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_pause(ctx: Context<SetPause>, paused: bool, pause_checks: bool) -> Result<()> {
+    let now = utc_now();
+    let config = &mut ctx.accounts.config;
+    config.bump = ctx.bumps.config;
+    config.paused = paused;
+    config.pause_checks = paused && pause_checks;
+    config.paused_at = if paused { Some(now) } else { None };
+
+    emit_event!(ctx, PauseChanged {
+        time: now,
+        paused,
+        pause_checks: ctx.accounts.config.pause_checks,
+        actor: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::state::config::ProgramConfig;
+use crate::state::file::{ChangeAction, File};
+use crate::state::role::*;
+use crate::utils::{allowed_authority, seeds::role_seed, utc_now, valid_rule};
+use crate::Errors::{self, InvalidRole};
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+#[instruction(template_data: RoleTemplateData)]
+pub struct SetRoleTemplate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&authority.key(), &file.authority) @ Errors::Unauthorized,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RoleTemplate::MAX_SIZE,
+        seeds = [b"role_template".as_ref(), role_seed(&template_data.role, &AddressType::Collection, &Some(template_data.collection)).as_ref(), file.id.key().as_ref()],
+        constraint = valid_rule(&template_data.role, true) @ InvalidRole,
+        bump
+    )]
+    pub role_template: Account<'info, RoleTemplate>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates (or updates the duration of) the template used by collection holders to claim a Wallet Role.
+pub fn set_role_template(ctx: Context<SetRoleTemplate>, template_data: RoleTemplateData) -> Result<()> {
+    if template_data.duration <= 0 {
+        return err!(Errors::InvalidRoleDuration);
+    }
+    let template = &mut ctx.accounts.role_template;
+    let action = if template.file_id == Pubkey::default() { ChangeAction::Created } else { ChangeAction::Updated };
+    template.file_id = ctx.accounts.file.id;
+    template.role = template_data.role;
+    template.collection = template_data.collection;
+    template.duration = template_data.duration;
+    template.bump = ctx.bumps.role_template;
+    ctx.accounts.file.add_child(&mut template.payer, ctx.accounts.authority.key());
+
+    emit_event!(ctx, RoleTemplateChanged {
+        time: utc_now(),
+        file_id: ctx.accounts.file.id,
+        action,
+        template: ctx.accounts.role_template.key(),
+        role_name: ctx.accounts.role_template.role.clone(),
+        collection: ctx.accounts.role_template.collection,
+        duration: ctx.accounts.role_template.duration,
+        actor: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
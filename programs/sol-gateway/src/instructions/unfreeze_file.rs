@@ -0,0 +1,34 @@
+use crate::state::file::*;
+use crate::utils::{allowed_authority, allowed_recovery, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct UnfreezeFile<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&signer.key(), &file.authority) || allowed_recovery(&signer.key(), &file) @ Errors::Unauthorized,
+        constraint = file.frozen_at.is_some() @ Errors::FileNotFrozen,
+    )]
+    pub file: Box<Account<'info, File>>,
+}
+
+pub fn unfreeze_file(ctx: Context<UnfreezeFile>) -> Result<()> {
+    let file = &mut ctx.accounts.file;
+    file.frozen_at = None;
+    file.frozen_namespaces = vec![];
+
+    emit_event!(ctx, FileFrozen {
+        time: utc_now(),
+        file_id: ctx.accounts.file.id,
+        frozen: false,
+        namespaces: vec![],
+        actor: ctx.accounts.signer.key(),
+    });
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::state::audit::*;
+use crate::state::config::ProgramConfig;
+use crate::state::file::File;
+use crate::utils::{allowed_authority, utc_now};
+use crate::Errors;
+use crate::emit_event;
+use anchor_lang::prelude::*;
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateAudit<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), file.id.key().as_ref()],
+        bump = file.bump,
+        constraint = allowed_authority(&authority.key(), &file.authority) @ Errors::Unauthorized,
+    )]
+    pub file: Box<Account<'info, File>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AuditLog::MAX_SIZE,
+        seeds = [b"audit".as_ref(), file.id.key().as_ref()],
+        bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Enables or disables the access audit log of the File (existing entries are always kept).
+pub fn update_audit(ctx: Context<UpdateAudit>, enabled: bool) -> Result<()> {
+    let audit_log = &mut ctx.accounts.audit_log;
+    audit_log.file_id = ctx.accounts.file.id;
+    audit_log.bump = ctx.bumps.audit_log;
+    ctx.accounts.file.add_child(&mut audit_log.payer, ctx.accounts.authority.key());
+    ctx.accounts.file.audit = enabled;
+
+    emit_event!(ctx, AuditChanged {
+        time: utc_now(),
+        file_id: ctx.accounts.file.id,
+        enabled,
+        actor: ctx.accounts.authority.key(),
+    });
+    Ok(())
+}
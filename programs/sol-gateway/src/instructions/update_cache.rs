@@ -1,29 +1,88 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::Errors;
 use crate::state::file::*;
-use crate::utils::utc_now;
+use crate::state::role::Role;
+use crate::state::rule::{Namespaces, Rule};
+use crate::utils::{parse_cache_updated, role_seed, rule_seed, utc_now};
+use crate::emit_event;
 use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 pub struct UpdateCache<'info> {
-    pub authority: Signer<'info>, // Only current Authority is allowed
+    #[account(mut)]
+    pub signer: Signer<'info>,
     #[account(
         mut,
-        has_one = authority,
         seeds = [b"file".as_ref(), file.id.key().as_ref()], 
         bump = file.bump,
     )]
-    pub file: Account<'info, File>,
+    pub file: Box<Account<'info, File>>,
+
+    /** Validation accounts (only required when signer is not the Authority) */
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn update_cache(ctx: Context<UpdateCache>, cache_updated: u8) -> Result<()> {
-    let file = &mut ctx.accounts.file;
+    let cache_updated = parse_cache_updated(cache_updated)?;
+    // File authority is always allowed, other signers require a role on the UpdateCache namespace
+    allowed(
+        &ctx.accounts.signer,
+        &ctx.accounts.file,
+        &ctx.accounts.sol_gateway_role,
+        &ctx.accounts.sol_gateway_rule,
+        &None,
+        &ctx.accounts.sol_gateway_token,
+        &ctx.accounts.sol_gateway_metadata,
+        &mut ctx.accounts.sol_gateway_seed,
+        &ctx.accounts.system_program,
+        AllowedRule {
+            file_id: ctx.accounts.file.id,
+            namespace: Namespaces::UpdateCache as u8,
+            resource: "cache".to_string(),
+            permission: if cache_updated == CacheUpdated::Roles { "roles" } else { "rules" }.to_string(),
+        },
+    )?;
+
     let now = utc_now();
-    if cache_updated == CacheUpdated::Roles as u8 {
-        file.roles_updated_at = now;
-    } else {
-        file.rules_updated_at = now;
-    }
-    emit!(FileChanged {
+    ctx.accounts.file.touch_cache(cache_updated, now);
+    emit_event!(ctx, FileChanged {
         time: now,
         file_id: ctx.accounts.file.id,
         authority: ctx.accounts.file.authority,
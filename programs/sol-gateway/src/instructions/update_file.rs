@@ -1,22 +1,98 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
 use crate::state::file::*;
-use crate::utils::{program_authority_field, utc_now, validate_string_len};
+use crate::state::role::Role;
+use crate::state::rule::{Namespaces, Rule};
+use crate::utils::{allowed_authority, allowed_recovery, program_authority_field, role_seed, rule_seed, utc_now, validate_string_len};
 use crate::Errors;
+use crate::emit_event;
 use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
 pub struct UpdateFile<'info> {
+    #[account(mut)]
     pub signer: Signer<'info>, // Only current Authority or Recovery key can update the Authority
     #[account(
         mut,
         seeds = [b"file".as_ref(), file.id.key().as_ref()], 
         bump = file.bump,
-        constraint = file.authority == signer.key() || (file.recovery.is_some() && file.recovery.unwrap() == signer.key())   @ Errors::UnauthorizedAuthorityUpdate,
     )]
     pub file: Box<Account<'info, File>>,
+
+    /** Validation accounts (only required when signer is neither the Authority nor the Recovery key) */
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        // The Authority and Recovery key can still rotate a leaked key while paused
+        constraint = !ProgramConfig::writes_paused(&config) || allowed_authority(&signer.key(), &file.authority) || allowed_recovery(&signer.key(), &file) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn update_file(ctx: Context<UpdateFile>, file_data: UpdateFileData) -> Result<()> {
+    let signer = ctx.accounts.signer.key();
+    let file = &ctx.accounts.file;
+    // Deleted Files can only be torn down
+    if file.deleted_at.is_some() {
+        return err!(Errors::FileDeleted);
+    }
+    let manager = allowed_authority(&signer, &file.authority) || allowed_recovery(&signer, file);
+    // Only current Authority or Recovery key can update the Authority
+    if !manager && (file_data.authority != file.authority || file_data.recovery != file.recovery) {
+        return err!(Errors::UnauthorizedAuthorityUpdate);
+    }
+    // Other fields can also be updated by roles allowed on the UpdateFile namespace
+    if !manager {
+        allowed(
+            &ctx.accounts.signer,
+            &ctx.accounts.file,
+            &ctx.accounts.sol_gateway_role,
+            &ctx.accounts.sol_gateway_rule,
+            &None,
+            &ctx.accounts.sol_gateway_token,
+            &ctx.accounts.sol_gateway_metadata,
+            &mut ctx.accounts.sol_gateway_seed,
+            &ctx.accounts.system_program,
+            AllowedRule {
+                file_id: ctx.accounts.file.id,
+                namespace: Namespaces::UpdateFile as u8,
+                resource: "file".to_string(),
+                permission: "update".to_string(),
+            },
+        )?;
+    }
+
     let file = &mut ctx.accounts.file;
     file.authority = file_data.authority;
     file.recovery = file_data.recovery;
@@ -31,8 +107,10 @@ pub fn update_file(ctx: Context<UpdateFile>, file_data: UpdateFileData) -> Resul
     file.size = file_data.size.unwrap_or(file.size);
     file.checksum = validate_string_len(&file_data.checksum, 0, 32)?;
     file.expires_at = file_data.expires_at.unwrap_or(file.expires_at);
+    file.max_roles = file_data.max_roles;
+    file.max_rules = file_data.max_rules;
 
-    emit!(FileChanged {
+    emit_event!(ctx, FileChanged {
         time: utc_now(),
         file_id: ctx.accounts.file.id,
         authority: ctx.accounts.file.authority,
@@ -1,23 +1,84 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::state::role::Role;
+use crate::state::rule::{Namespaces, Rule};
 use crate::{
-    state::file::*, utils::allowed_authority, Errors, FileMetadata, MetadataData, MetadataUpdated,
+    state::file::*,
+    utils::{resize_account, role_seed, rule_seed, changed_metadata_keys, validate_metadata, validate_schema},
+    Errors, FileMetadata, MetadataSchema, MetadataData, MetadataUpdated,
 };
+use crate::emit_event;
 use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
 
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
 #[derive(Accounts)]
+#[instruction(metadata_data: MetadataData)]
 pub struct UpdateFileMetadata<'info> {
+    #[account(mut)]
     pub signer: Signer<'info>,
     #[account(
         seeds = [b"file".as_ref(), file.id.key().as_ref()],
         bump = file.bump,
-        constraint = file.authority == signer.key() || (file.recovery.is_some() && file.recovery.unwrap() == signer.key()) @ Errors::UnauthorizedMetadataUpdate,
     )]
     pub file: Box<Account<'info, File>>,
     #[account(
         mut,
         seeds = [b"metadata".as_ref(), file.id.key().as_ref()],
         bump = file_metadata.bump,
+        constraint = FileMetadata::is_typed(&file_metadata.to_account_info()) @ Errors::MetadataNotMigrated,
     )]
     pub file_metadata: Account<'info, FileMetadata>,
+    /// CHECK: Schema of the File metadata (when initialized)
+    #[account(
+        seeds = [b"schema".as_ref(), file.id.key().as_ref()],
+        bump,
+    )]
+    pub metadata_schema: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub rent_payer: Signer<'info>, // Pays the extra rent when metadata grows
+    /// CHECK: Receives the rent refunded when metadata shrinks, the account that paid for the metadata
+    #[account(
+        mut,
+        constraint = rent_receiver.key() == file_metadata.payer @ Errors::InvalidRentReceiver,
+    )]
+    pub rent_receiver: UncheckedAccount<'info>,
+
+    /** Validation accounts (only required when signer is not the Authority) */
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program = metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -25,20 +86,52 @@ pub fn update_file_metadata(
     ctx: Context<UpdateFileMetadata>,
     metadata_data: MetadataData,
 ) -> Result<()> {
+    let keys = changed_metadata_keys(&ctx.accounts.file_metadata.metadata, &metadata_data.metadata);
+    // File authority is always allowed, other signers require a role on the UpdateMetadata namespace
+    // for every modified key (or on every key "*" when nothing changes, e.g. reordered entries)
+    let permissions = if keys.is_empty() { vec!["*".to_string()] } else { keys.clone() };
+    for key in permissions {
+        allowed(
+            &ctx.accounts.signer,
+            &ctx.accounts.file,
+            &ctx.accounts.sol_gateway_role,
+            &ctx.accounts.sol_gateway_rule,
+            &None,
+            &ctx.accounts.sol_gateway_token,
+            &ctx.accounts.sol_gateway_metadata,
+            &mut ctx.accounts.sol_gateway_seed,
+            &ctx.accounts.system_program,
+            AllowedRule {
+                file_id: ctx.accounts.file.id,
+                namespace: Namespaces::UpdateMetadata as u8,
+                resource: "metadata".to_string(),
+                permission: key,
+            },
+        )?;
+    }
+
     let file = &ctx.accounts.file;
     let file_metadata = &mut ctx.accounts.file_metadata;
-
-    require!(
-        allowed_authority(&ctx.accounts.signer.key(), &file.authority),
-        Errors::UnauthorizedMetadataUpdate
-    );
-
+    validate_metadata(&metadata_data.metadata)?;
+    if let Some(schema) = MetadataSchema::load(&ctx.accounts.metadata_schema)? {
+        validate_schema(&metadata_data.metadata, &schema.fields)?;
+    }
     file_metadata.metadata = metadata_data.metadata;
+    // Extra rent is paid by the rent payer, the rent no longer required goes back to the metadata payer
+    let space = FileMetadata::space(&file_metadata.metadata);
+    resize_account(
+        &file_metadata.to_account_info(),
+        space,
+        &ctx.accounts.rent_payer.to_account_info(),
+        &ctx.accounts.rent_receiver.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
 
-    emit!(MetadataUpdated {
+    emit_event!(ctx, MetadataUpdated {
         time: Clock::get()?.unix_timestamp,
         file_id: file.id,
         authority: ctx.accounts.file.authority,
+        keys,
     });
 
     Ok(())
@@ -0,0 +1,102 @@
+use crate::instructions::allowed::{allowed, AllowedRule};
+use crate::metadata_program;
+use crate::state::config::ProgramConfig;
+use crate::Errors;
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
+use crate::state::role::{Role, RolesChanged, UpdateRoleData};
+use crate::state::rule::Namespaces;
+use crate::state::rule::Rule;
+use crate::utils::{seeds::{role_seed, rule_seed}, utc_now};
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateRole<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"role".as_ref(), role_seed(&role.role, &role.address_type, &role.address).as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = role.bump,
+    )]
+    pub role: Account<'info, Role>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program =metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_role(ctx: Context<UpdateRole>, update_role_data: UpdateRoleData) -> Result<()> {
+    allowed(
+        &ctx.accounts.signer,
+        &ctx.accounts.sol_gateway_file,
+        &ctx.accounts.sol_gateway_role,
+        &ctx.accounts.sol_gateway_rule,
+        &None,
+        &ctx.accounts.sol_gateway_token,
+        &ctx.accounts.sol_gateway_metadata,
+        &mut ctx.accounts.sol_gateway_seed,
+        &ctx.accounts.system_program,
+        AllowedRule {
+            file_id: ctx.accounts.sol_gateway_file.id.key(),
+            namespace: Namespaces::UpdateAssignRole as u8,
+            resource: ctx.accounts.role.address_type.to_string(),
+            permission: ctx.accounts.role.role.clone(),
+        },
+    )?;
+
+    let role = &mut ctx.accounts.role;
+    role.expires_at = update_role_data.expires_at;
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Updated, &*ctx.accounts.role)?;
+
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Roles, now);
+    }
+    emit_event!(ctx, RolesChanged::new(
+        now,
+        ChangeAction::Updated,
+        ctx.accounts.role.key(),
+        &ctx.accounts.role,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
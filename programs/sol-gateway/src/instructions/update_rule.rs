@@ -0,0 +1,157 @@
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+use crate::instructions::allowed::{allowed, allowed_without_fee, AllowedRule};
+use crate::state::config::ProgramConfig;
+use crate::Errors;
+use crate::state::file::{CacheUpdated, ChangeAction, File, Seed};
+use crate::state::permissions::PermissionRegistry;
+use crate::state::role::Role;
+use crate::state::rule::*;
+use crate::utils::{rule_permissions, utc_now, seeds::{rule_seed, role_seed}};
+use crate::emit_event;
+use anchor_lang::prelude::*;
+use crate::metadata_program;
+
+
+#[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateRule<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"rule".as_ref(), rule_seed(rule.namespace, &rule.role, &rule.resource, &rule.permission).as_ref(), sol_gateway_file.id.key().as_ref()], 
+        bump = rule.bump,
+    )]
+    pub rule: Account<'info, Rule>,
+    #[account(
+        mut,
+        seeds = [b"file".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = sol_gateway_file.bump,
+    )]
+    pub sol_gateway_file: Box<Account<'info, File>>,
+    #[account(
+        seeds = [b"permissions".as_ref(), sol_gateway_file.id.key().as_ref()],
+        bump = permission_registry.bump,
+    )]
+    pub permission_registry: Option<Box<Account<'info, PermissionRegistry>>>,
+    #[account(
+        seeds = [b"role".as_ref(), role_seed(&sol_gateway_role.role, &sol_gateway_role.address_type, &sol_gateway_role.address).as_ref(), sol_gateway_role.file_id.key().as_ref()],
+        bump = sol_gateway_role.bump
+    )]
+    pub sol_gateway_role: Option<Box<Account<'info, Role>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule.namespace, &sol_gateway_rule.role, &sol_gateway_rule.resource, &sol_gateway_rule.permission).as_ref(), sol_gateway_rule.file_id.key().as_ref()],
+        bump = sol_gateway_rule.bump,
+    )]
+    pub sol_gateway_rule: Option<Box<Account<'info, Rule>>>,
+    #[account(
+        seeds = [b"rule".as_ref(), rule_seed(sol_gateway_rule2.namespace, &sol_gateway_rule2.role, &sol_gateway_rule2.resource, &sol_gateway_rule2.permission).as_ref(), sol_gateway_rule2.file_id.key().as_ref()],
+        bump = sol_gateway_rule2.bump,
+    )]
+    pub sol_gateway_rule2: Option<Box<Account<'info, Rule>>>,
+    #[account()]
+    pub sol_gateway_token: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"metadata", metadata_program::ID.as_ref(), sol_gateway_metadata.mint.key().as_ref()],
+        seeds::program =metadata_program::ID,
+        bump,
+    )]
+    pub sol_gateway_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 9, // Account discriminator + initialized
+        seeds = [b"seed".as_ref(), signer.key.as_ref()],
+        bump
+    )]
+    pub sol_gateway_seed: Option<Account<'info, Seed>>,
+    /// CHECK: Program pause state (not paused while it was never initialized)
+    #[account(
+        seeds = [b"config".as_ref()],
+        bump,
+        constraint = !ProgramConfig::writes_paused(&config) @ Errors::ProgramPaused,
+    )]
+    pub config: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_rule(
+    ctx: Context<UpdateRule>,
+    update_rule_data: UpdateRuleData
+) -> Result<()> {
+      // Checks if is allowed to update a rule for this specific Namespace and Role.
+      allowed(
+        &ctx.accounts.signer,
+        &ctx.accounts.sol_gateway_file,
+        &ctx.accounts.sol_gateway_role,
+        &ctx.accounts.sol_gateway_rule,
+        &None,
+        &ctx.accounts.sol_gateway_token,
+        &ctx.accounts.sol_gateway_metadata,
+        &mut ctx.accounts.sol_gateway_seed,
+        &ctx.accounts.system_program,
+        AllowedRule {
+            file_id: ctx.accounts.sol_gateway_file.id.key(),
+            namespace: Namespaces::UpdateRuleNSRole as u8,
+            resource: ctx.accounts.rule.namespace.to_string(),
+            permission: ctx.accounts.rule.role.to_string(),
+        },
+    )?;
+    // // Checks if is allowed to update a rule for this specific Resource and Permission (each one of them on permission sets).
+    let permissions = rule_permissions(
+        &ctx.accounts.rule.permission,
+        ctx.accounts.rule.permissions,
+        ctx.accounts.permission_registry.as_deref().map(|registry| &**registry),
+    )?;
+    // The fee is only paid for the first permission of the set.
+    for (index, permission) in permissions.into_iter().enumerate() {
+        let allowed_rule = AllowedRule {
+            file_id: ctx.accounts.sol_gateway_file.id.key(),
+            namespace: Namespaces::UpdateRuleResourcePerm as u8,
+            resource: ctx.accounts.rule.resource.to_string(),
+            permission,
+        };
+        if index == 0 {
+            allowed(
+                &ctx.accounts.signer,
+                &ctx.accounts.sol_gateway_file,
+                &ctx.accounts.sol_gateway_role,
+                &ctx.accounts.sol_gateway_rule2,
+                &None,
+                &ctx.accounts.sol_gateway_token,
+                &ctx.accounts.sol_gateway_metadata,
+                &mut ctx.accounts.sol_gateway_seed,
+                &ctx.accounts.system_program,
+                allowed_rule,
+            )?;
+        } else {
+            allowed_without_fee(
+                &ctx.accounts.signer,
+                &ctx.accounts.sol_gateway_file,
+                &ctx.accounts.sol_gateway_role,
+                &ctx.accounts.sol_gateway_rule2,
+                &None,
+                &ctx.accounts.sol_gateway_token,
+                &ctx.accounts.sol_gateway_metadata,
+                allowed_rule,
+            )?;
+        }
+    }
+
+    let rule = &mut ctx.accounts.rule;
+    rule.expires_at = update_rule_data.expires_at;
+    ctx.accounts.sol_gateway_file.record_policy(ChangeAction::Updated, &*ctx.accounts.rule)?;
+
+    let now = utc_now();
+    if ctx.accounts.sol_gateway_file.cached {
+        ctx.accounts.sol_gateway_file.touch_cache(CacheUpdated::Rules, now);
+    }
+    emit_event!(ctx, RulesChanged::new(
+        now,
+        ChangeAction::Updated,
+        ctx.accounts.rule.key(),
+        &ctx.accounts.rule,
+        ctx.accounts.signer.key(),
+    ));
+    Ok(())
+}
@@ -26,8 +26,22 @@ pub mod sol_gateway {
         instructions::update_file::update_file(ctx, file_data)
     }
 
-    pub fn delete_file(ctx: Context<DeleteFile>) -> Result<()> {
-        instructions::delete_file::delete_file(ctx)
+    /**
+     * Closes the File. Files with Roles, Rules or other accounts are marked as deleted when
+     * `force` is set, and closed once "close_file_accounts" has closed them.
+     */
+    pub fn delete_file(ctx: Context<DeleteFile>, force: bool) -> Result<()> {
+        instructions::delete_file::delete_file(ctx, force)
+    }
+
+    /**
+     * Closes Roles, Rules and other accounts of a deleted File (in batches, received as remaining accounts),
+     * returning the rent of each account to its payer.
+     */
+    pub fn close_file_accounts<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseFileAccounts<'info>>,
+    ) -> Result<()> {
+        instructions::close_file_accounts::close_file_accounts(ctx)
     }
 
     pub fn update_file_metadata(
@@ -37,6 +51,40 @@ pub mod sol_gateway {
         instructions::update_metadata::update_file_metadata(ctx, metadata_data)
     }
 
+    /**
+     * Adds a single metadata entry, or replaces the value of an existing key.
+     */
+    pub fn set_metadata_entry(ctx: Context<SetMetadataEntry>, entry: Metadata) -> Result<()> {
+        instructions::set_metadata_entry::set_metadata_entry(ctx, entry)
+    }
+
+    /**
+     * Removes a single metadata entry.
+     */
+    pub fn remove_metadata_entry(ctx: Context<RemoveMetadataEntry>, key: String) -> Result<()> {
+        instructions::remove_metadata_entry::remove_metadata_entry(ctx, key)
+    }
+
+    /**
+     * Sets the schema (required keys, types and max lengths) enforced on the File metadata.
+     */
+    pub fn set_metadata_schema(
+        ctx: Context<SetMetadataSchema>,
+        schema_data: MetadataSchemaData,
+    ) -> Result<()> {
+        instructions::set_metadata_schema::set_metadata_schema(ctx, schema_data)
+    }
+
+    /**
+     * Registers permissions on the File, so a single Rule can grant a set of them (bitmask).
+     */
+    pub fn add_permissions(
+        ctx: Context<AddPermissions>,
+        permissions_data: PermissionsData,
+    ) -> Result<()> {
+        instructions::add_permissions::add_permissions(ctx, permissions_data)
+    }
+
     pub fn add_rule(ctx: Context<AddRule>, rule_data: RuleData) -> Result<()> {
         instructions::add_rule::add_rule(ctx, rule_data)
     }
@@ -45,6 +93,13 @@ pub mod sol_gateway {
         instructions::delete_rule::delete_rule(ctx)
     }
 
+    /**
+     * Updates the mutable fields of a Rule (e.g. expiration) without deleting it.
+     */
+    pub fn update_rule(ctx: Context<UpdateRule>, update_rule_data: UpdateRuleData) -> Result<()> {
+        instructions::update_rule::update_rule(ctx, update_rule_data)
+    }
+
     pub fn assign_role(ctx: Context<AssignRole>, assign_role_data: AssignRoleData) -> Result<()> {
         instructions::assign_role::assign_role(ctx, assign_role_data)
     }
@@ -53,6 +108,149 @@ pub mod sol_gateway {
         instructions::delete_assigned_role::delete_assigned_role(ctx)
     }
 
+    /**
+     * Updates the mutable fields of an assigned Role (e.g. expiration) without deleting it.
+     */
+    pub fn update_role(ctx: Context<UpdateRole>, update_role_data: UpdateRoleData) -> Result<()> {
+        instructions::update_role::update_role(ctx, update_role_data)
+    }
+
+    /**
+     * Closes a Wallet Role by its own holder, so wallets can give up their access.
+     */
+    pub fn renounce_role(ctx: Context<RenounceRole>) -> Result<()> {
+        instructions::renounce_role::renounce_role(ctx)
+    }
+
+    /**
+     * Creates or updates the template used by holders of a verified collection NFT to claim
+     * a time-bound Wallet Role. Only the File authority can manage templates.
+     */
+    pub fn set_role_template(ctx: Context<SetRoleTemplate>, template_data: RoleTemplateData) -> Result<()> {
+        instructions::set_role_template::set_role_template(ctx, template_data)
+    }
+
+    pub fn delete_role_template(ctx: Context<DeleteRoleTemplate>) -> Result<()> {
+        instructions::delete_role_template::delete_role_template(ctx)
+    }
+
+    /**
+     * Assigns the Wallet Role of a template to the signer once it proves owning a token of
+     * the template collection. The Role expires after the template duration, and the same token
+     * can't claim the template again until then. Claiming again once the Role has expired
+     * renews the existing Role.
+     */
+    pub fn claim_role(ctx: Context<ClaimRole>) -> Result<()> {
+        instructions::claim_role::claim_role(ctx)
+    }
+
+    /**
+     * Creates a pending request of a Wallet Role for the signer (with an optional justification
+     * and expiration), to be approved or rejected by roles on the ReviewRequest namespace.
+     */
+    pub fn request_role(ctx: Context<RequestRole>, request_data: RoleRequestData) -> Result<()> {
+        instructions::request_role::request_role(ctx, request_data)
+    }
+
+    /**
+     * Assigns the requested Role and closes the request.
+     */
+    pub fn approve_request(ctx: Context<ApproveRequest>) -> Result<()> {
+        instructions::approve_request::approve_request(ctx)
+    }
+
+    /**
+     * Closes the request without assigning the Role. The requesting wallet can also cancel it.
+     */
+    pub fn reject_request(ctx: Context<RejectRequest>) -> Result<()> {
+        instructions::reject_request::reject_request(ctx)
+    }
+
+    /**
+     * Moves a Rule created with the legacy seeds to its collision-free address, adding it
+     * to the File counters, policy hash and registry.
+     */
+    pub fn migrate_rule(ctx: Context<MigrateRule>) -> Result<()> {
+        instructions::migrate_rule::migrate_rule(ctx)
+    }
+
+    /**
+     * Moves a Role created with the legacy seeds to its collision-free address, adding it
+     * to the File counters, policy hash and registry.
+     */
+    pub fn migrate_role(ctx: Context<MigrateRole>) -> Result<()> {
+        instructions::migrate_role::migrate_role(ctx)
+    }
+
+    /**
+     * Lists a Role created before the File registry on it, so it can be deleted or renounced.
+     */
+    pub fn register_role(ctx: Context<RegisterRole>) -> Result<()> {
+        instructions::register_role::register_role(ctx)
+    }
+
+    /**
+     * Lists a Rule created before the File registry on it, so it can be deleted.
+     */
+    pub fn register_rule(ctx: Context<RegisterRule>) -> Result<()> {
+        instructions::register_rule::register_rule(ctx)
+    }
+
+    /**
+     * Grows a File stored without policy hash and counters, its legacy Roles and Rules are
+     * then added to them through "migrate_role" and "migrate_rule" until "complete_migration".
+     */
+    pub fn migrate_file(ctx: Context<MigrateFile>) -> Result<()> {
+        instructions::migrate_file::migrate_file(ctx)
+    }
+
+    /**
+     * Confirms every legacy Role and Rule of a migrated File was migrated, so the File can be deleted
+     * once its counted accounts are closed.
+     */
+    pub fn complete_migration(ctx: Context<CompleteMigration>) -> Result<()> {
+        instructions::complete_migration::complete_migration(ctx)
+    }
+
+    /**
+     * Rewrites a FileMetadata account stored with string only values into typed values.
+     */
+    pub fn migrate_metadata(ctx: Context<MigrateMetadata>) -> Result<()> {
+        instructions::migrate_metadata::migrate_metadata(ctx)
+    }
+
+    /**
+     * Enables or disables recording successful access checks on the File's audit log.
+     */
+    pub fn update_audit(ctx: Context<UpdateAudit>, enabled: bool) -> Result<()> {
+        instructions::update_audit::update_audit(ctx, enabled)
+    }
+
+    /**
+     * Pauses (or resumes) every state-mutating instruction across all Files, and optionally
+     * "allowed" and "check" too. Emergency actions are still possible while paused: freezing Files,
+     * deleting Roles and Rules, renouncing Roles and File updates by the Authority or Recovery key.
+     * Only the PROGRAM_AUTHORITY can call it.
+     */
+    pub fn set_pause(ctx: Context<SetPause>, paused: bool, pause_checks: bool) -> Result<()> {
+        instructions::set_pause::set_pause(ctx, paused, pause_checks)
+    }
+
+    /**
+     * Emergency switch: access checks are denied to everyone but the File authority,
+     * except for the given namespaces.
+     */
+    pub fn freeze_file(ctx: Context<FreezeFile>, namespaces: Vec<u8>) -> Result<()> {
+        instructions::freeze_file::freeze_file(ctx, namespaces)
+    }
+
+    /**
+     * Restores access checks of a frozen File.
+     */
+    pub fn unfreeze_file(ctx: Context<UnfreezeFile>) -> Result<()> {
+        instructions::unfreeze_file::unfreeze_file(ctx)
+    }
+
     /**
      * Updates either file.roles_updated_at or file.rules_updated_at fields, so clients
      * can keep track and cache roles & rules accordingly.
@@ -62,8 +260,9 @@ pub mod sol_gateway {
     }
 
     /**
-     * Checks if the current user is authorized to run the instruction,
-     * throwing "Unauthorized" error otherwise.
+     * Checks if the current user is authorized to run the instruction, throwing
+     * the error of the denial reason otherwise (e.g. "RoleExpired", "NamespaceMismatch").
+     * Successful checks are recorded on the audit log when enabled on the File.
      */
     pub fn allowed(ctx: Context<Allowed>, allowed_rule: AllowedRule) -> Result<()> {
         instructions::allowed::allowed(
@@ -71,11 +270,50 @@ pub mod sol_gateway {
             &ctx.accounts.sol_gateway_file,
             &ctx.accounts.sol_gateway_role,
             &ctx.accounts.sol_gateway_rule,
+            &ctx.accounts.sol_gateway_permissions,
             &ctx.accounts.sol_gateway_token,
             &ctx.accounts.sol_gateway_metadata,
             &mut ctx.accounts.sol_gateway_seed,
             &ctx.accounts.system_program,
-            allowed_rule,
+            allowed_rule.clone(),
+        )?;
+        instructions::allowed::record_access(
+            &ctx.accounts.signer,
+            &ctx.accounts.sol_gateway_file,
+            &ctx.accounts.sol_gateway_role,
+            &mut ctx.accounts.sol_gateway_audit,
+            &allowed_rule,
         )
     }
+
+    /**
+     * Checks if the current user is authorized without failing when access is denied,
+     * the decision (granted, reason, matched rule and role expiration) is returned as
+     * return data, so calling programs can branch on it. Denied checks don't keep the seed
+     * account (its rent goes back to the signer), so only granted checks initialize it.
+     */
+    pub fn check(ctx: Context<Allowed>, allowed_rule: AllowedRule) -> Result<AccessDecision> {
+        let decision = instructions::allowed::check(
+            &ctx.accounts.signer,
+            &ctx.accounts.sol_gateway_file,
+            &ctx.accounts.sol_gateway_role,
+            &ctx.accounts.sol_gateway_rule,
+            &ctx.accounts.sol_gateway_permissions,
+            &ctx.accounts.sol_gateway_token,
+            &ctx.accounts.sol_gateway_metadata,
+            &mut ctx.accounts.sol_gateway_seed,
+            &ctx.accounts.system_program,
+            allowed_rule.clone(),
+        )?;
+        if decision.granted {
+            instructions::allowed::record_access(
+                &ctx.accounts.signer,
+                &ctx.accounts.sol_gateway_file,
+                &ctx.accounts.sol_gateway_role,
+                &mut ctx.accounts.sol_gateway_audit,
+                &allowed_rule,
+            )?;
+        }
+        Ok(decision)
+    }
 }
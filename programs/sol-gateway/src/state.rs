@@ -1,9 +1,19 @@
+pub use audit::*;
+pub use config::*;
 pub use file::*;
 pub use metadata::*;
+pub use permissions::*;
+pub use registry::*;
+pub use request::*;
 pub use role::*;
 pub use rule::*;
 
+pub mod audit;
+pub mod config;
 pub mod file;
 pub mod metadata;
+pub mod permissions;
+pub mod registry;
+pub mod request;
 pub mod role;
 pub mod rule;
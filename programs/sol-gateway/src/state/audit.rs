@@ -0,0 +1,64 @@
+use crate::MAX_NAME_LENGTH;
+use anchor_lang::prelude::*;
+
+/**
+ * Types
+ */
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    pub signer: Pubkey,
+    pub namespace: u8,
+    pub resource: String,
+    pub permission: String,
+    pub role: String, // Empty when access was granted to the File authority
+    pub time: i64,
+}
+
+impl AuditEntry {
+    pub const MAX_SIZE: usize = 32 + 1 + (4 + MAX_NAME_LENGTH) * 3 + 8;
+}
+
+/**
+ * Accounts
+ */
+
+// Per File ring buffer recording the latest successful access checks. Once full, the
+// oldest entry is overwritten, `sequence` keeps the total number of recorded entries.
+#[account]
+pub struct AuditLog {
+    pub file_id: Pubkey,
+    pub sequence: u64,
+    pub entries: Vec<AuditEntry>,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl AuditLog {
+    pub const CAPACITY: usize = 32;
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 4 + AuditEntry::MAX_SIZE * Self::CAPACITY + 1 + 32;
+
+    /// Appends the entry, overwriting the oldest one when the buffer is full.
+    pub fn record(&mut self, entry: AuditEntry) {
+        let index = (self.sequence % Self::CAPACITY as u64) as usize;
+        if self.entries.len() < Self::CAPACITY {
+            self.entries.push(entry);
+        } else {
+            self.entries[index] = entry;
+        }
+        self.sequence += 1;
+    }
+}
+
+/**
+ * Events
+ */
+
+#[event]
+pub struct AuditChanged {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub enabled: bool,
+    pub actor: Pubkey,
+}
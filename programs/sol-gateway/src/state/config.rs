@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+/**
+ * Accounts
+ */
+
+// Program-wide settings, controlled by the PROGRAM_AUTHORITY. The account is only created when first
+// updated, so a missing config means the program is not paused.
+#[account]
+pub struct ProgramConfig {
+    pub paused: bool, // State-mutating instructions are rejected while paused
+    pub pause_checks: bool, // "allowed" and "check" are also rejected while paused
+    pub paused_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl ProgramConfig {
+    pub const MAX_SIZE: usize = 8 + 1 + 1 + 9 + 1;
+
+    /// Config stored on the provided account (None when it was never initialized).
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        if info.owner != &crate::ID {
+            return err!(ErrorCode::AccountOwnedByWrongProgram);
+        }
+        Ok(Some(Self::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+
+    /// Whether state-mutating instructions are paused.
+    pub fn writes_paused(info: &AccountInfo) -> bool {
+        matches!(Self::load(info), Ok(Some(config)) if config.paused)
+    }
+
+    /// Whether access checks are paused.
+    pub fn checks_paused(info: &AccountInfo) -> bool {
+        matches!(Self::load(info), Ok(Some(config)) if config.paused && config.pause_checks)
+    }
+}
+
+/**
+ * Events
+ */
+
+#[event]
+pub struct PauseChanged {
+    pub time: i64,
+    pub paused: bool,
+    pub pause_checks: bool,
+    pub actor: Pubkey,
+}
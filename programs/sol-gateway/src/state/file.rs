@@ -1,5 +1,9 @@
 use crate::state::metadata::*;
+use crate::utils::{chain_policy_hash, PolicyAccount};
+use crate::Errors;
+use crate::MAX_FROZEN_NAMESPACES;
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 ///  AccountTypes:
 ///     0 => Basic  (Files with default fees)
@@ -14,11 +18,23 @@ pub enum AccountTypes {
 ///     0 => Roles (When roles change)
 ///     1 => Rules   (When rules change)
 #[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CacheUpdated {
     Roles = 0,
     Rules = 1,
 }
 
+///  ChangeActions (included on RolesChanged and RulesChanged events):
+///     Created => The account was created
+///     Deleted => The account was closed
+///     Updated => The account fields were modified
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum ChangeAction {
+    Created,
+    Deleted,
+    Updated,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Debug)]
 pub struct FileData {
     pub id: Pubkey,
@@ -29,6 +45,7 @@ pub struct FileData {
     pub checksum: String,
     pub expires_at: i64,
     pub metadata: Option<Vec<Metadata>>,
+    pub schema: Option<Vec<SchemaField>>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Debug)]
@@ -42,6 +59,8 @@ pub struct UpdateFileData {
     pub checksum: String,
     pub account_type: u8,
     pub expires_at: Option<i64>,
+    pub max_roles: Option<u32>,
+    pub max_rules: Option<u32>,
 }
 
 #[account]
@@ -59,10 +78,169 @@ pub struct File {
     pub checksum: String,
     pub account_type: u8,
     pub expires_at: i64,
+    pub audit: bool, // Successful access checks are recorded on the File's AuditLog
+    // Chained hash of every Role and Rule change (RolesChanged and RulesChanged events), starting from zero
+    // when the File is created or migrated. Auditors replay the events to get the policy enforced by a hash.
+    pub policy_hash: [u8; 32],
+    pub roles_count: u32,
+    pub rules_count: u32,
+    pub max_roles: Option<u32>, // No limit when None
+    pub max_rules: Option<u32>, // No limit when None
+    pub deleted_at: Option<i64>, // Set when the File is deleted while it still has Roles or Rules
+    pub frozen_at: Option<i64>, // Only the authority is allowed while frozen
+    pub frozen_namespaces: Vec<u8>, // Namespaces still allowed while frozen
+    pub children_count: u32, // Accounts of the File other than Roles and Rules (metadata, registries, templates...)
+    pub migrating: bool, // Migrated legacy File whose Roles and Rules may not be counted yet (see "complete_migration")
 }
 
 impl File {
+    pub const MAX_SIZE: usize = 162 + 8 + 4 + 32 + 32 + 4 + 4 + 5 + 5 + 9 + 9 + (4 + MAX_FROZEN_NAMESPACES) + 4 + 1;
+
+    /// Counts a new Role, failing when the File maximum is reached.
+    pub fn add_role(&mut self) -> Result<()> {
+        if matches!(self.max_roles, Some(max) if self.roles_count >= max) {
+            return err!(Errors::TooManyRoles);
+        }
+        self.roles_count += 1;
+        Ok(())
+    }
+
+    /// Counts a new Rule, failing when the File maximum is reached.
+    pub fn add_rule(&mut self) -> Result<()> {
+        if matches!(self.max_rules, Some(max) if self.rules_count >= max) {
+            return err!(Errors::TooManyRules);
+        }
+        self.rules_count += 1;
+        Ok(())
+    }
+
+    // Only registered Roles and Rules are counted, see `Role::registered`.
+    pub fn remove_role(&mut self) {
+        self.roles_count = self.roles_count.saturating_sub(1);
+    }
+
+    pub fn remove_rule(&mut self) {
+        self.rules_count = self.rules_count.saturating_sub(1);
+    }
+
+    /// Counts an account created for the File, recording the account paying its rent. Accounts
+    /// reused through "init_if_needed" already have a payer, so they are only counted once.
+    pub fn add_child(&mut self, payer: &mut Pubkey, rent_payer: Pubkey) {
+        if *payer == Pubkey::default() {
+            *payer = rent_payer;
+            self.children_count += 1;
+        }
+    }
+
+    pub fn remove_child(&mut self) {
+        self.children_count = self.children_count.saturating_sub(1);
+    }
+
+    /// Roles, Rules and other accounts must be closed before the File, or their rent would be lost.
+    /// Counters of Files still migrating may miss legacy accounts, so they are never empty.
+    pub fn is_empty(&self) -> bool {
+        !self.migrating && self.roles_count == 0 && self.rules_count == 0 && self.children_count == 0
+    }
+
+    /// Records a Role or Rule change on the File policy hash, with the account state after the change
+    /// (the removed state when deleted). Each RolesChanged or RulesChanged event must be recorded once.
+    pub fn record_policy<T: PolicyAccount>(&mut self, action: ChangeAction, account: &T) -> Result<()> {
+        let leaf = account.policy_leaf()?;
+        self.policy_hash = chain_policy_hash(&self.policy_hash, action, &leaf);
+        Ok(())
+    }
+
+    /// Sets the Roles or Rules cache timestamp, so clients know when to invalidate their cache.
+    pub fn touch_cache(&mut self, cache_updated: CacheUpdated, now: i64) {
+        match cache_updated {
+            CacheUpdated::Roles => self.roles_updated_at = now,
+            CacheUpdated::Rules => self.rules_updated_at = now,
+        }
+    }
+}
+
+/// File layout before the policy hash and counters were introduced, only used to migrate legacy accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyFile {
+    pub id: Pubkey,
+    pub authority: Pubkey,
+    pub recovery: Option<Pubkey>,
+    pub bump: u8,
+    pub name: String,
+    pub roles_updated_at: i64,
+    pub rules_updated_at: i64,
+    pub cached: bool,
+    pub fee: Option<u64>,
+    pub size: u64,
+    pub checksum: String,
+    pub account_type: u8,
+    pub expires_at: i64,
+    pub audit: bool,
+}
+
+impl LegacyFile {
     pub const MAX_SIZE: usize = 162 + 8 + 4 + 32;
+
+    /// Legacy File with an empty policy hash and no Roles or Rules counted, until its migration is completed.
+    pub fn to_file(&self) -> File {
+        File {
+            id: self.id,
+            authority: self.authority,
+            recovery: self.recovery,
+            bump: self.bump,
+            name: self.name.clone(),
+            roles_updated_at: self.roles_updated_at,
+            rules_updated_at: self.rules_updated_at,
+            cached: self.cached,
+            fee: self.fee,
+            size: self.size,
+            checksum: self.checksum.clone(),
+            account_type: self.account_type,
+            expires_at: self.expires_at,
+            audit: self.audit,
+            policy_hash: [0; 32],
+            roles_count: 0,
+            rules_count: 0,
+            max_roles: None,
+            max_rules: None,
+            deleted_at: None,
+            frozen_at: None,
+            frozen_namespaces: vec![],
+            children_count: 0,
+            migrating: true,
+        }
+    }
+}
+
+impl Discriminator for LegacyFile {
+    const DISCRIMINATOR: [u8; 8] = File::DISCRIMINATOR;
+}
+
+impl Owner for LegacyFile {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+// Nothing is written back on exit, the migration overwrites the account with the new layout.
+impl AccountSerialize for LegacyFile {}
+
+impl AccountDeserialize for LegacyFile {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Self::DISCRIMINATOR.len() {
+            return err!(ErrorCode::AccountDiscriminatorNotFound);
+        }
+        if buf[..8] != Self::DISCRIMINATOR {
+            return err!(ErrorCode::AccountDiscriminatorMismatch);
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
 }
 
 #[event]
@@ -73,6 +251,26 @@ pub struct FileChanged {
     pub authority: Pubkey,
 }
 
+#[event]
+pub struct FileAccountsClosed {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub collector: Pubkey,
+    pub actor: Pubkey,
+}
+
+#[event]
+pub struct FileFrozen {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub frozen: bool,
+    pub namespaces: Vec<u8>,
+    pub actor: Pubkey,
+}
+
 #[account]
 pub struct Seed {
     pub initialized: bool,
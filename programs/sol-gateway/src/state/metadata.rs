@@ -1,13 +1,58 @@
+use crate::{MAX_METADATA_ENTRIES, MAX_METADATA_KEY_LENGTH};
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 /**
  * Types
  */
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    String(String),
+    U64(u64),
+    I64(i64),
+    Bool(bool),
+    Pubkey(Pubkey),
+    Bytes(Vec<u8>),
+}
+
+impl MetadataValue {
+    /// Serialized size (variant + value).
+    pub fn space(&self) -> usize {
+        1 + match self {
+            MetadataValue::String(value) => 4 + value.len(),
+            MetadataValue::U64(_) | MetadataValue::I64(_) => 8,
+            MetadataValue::Bool(_) => 1,
+            MetadataValue::Pubkey(_) => 32,
+            MetadataValue::Bytes(value) => 4 + value.len(),
+        }
+    }
+
+    pub fn value_type(&self) -> MetadataType {
+        match self {
+            MetadataValue::String(_) => MetadataType::String,
+            MetadataValue::U64(_) => MetadataType::U64,
+            MetadataValue::I64(_) => MetadataType::I64,
+            MetadataValue::Bool(_) => MetadataType::Bool,
+            MetadataValue::Pubkey(_) => MetadataType::Pubkey,
+            MetadataValue::Bytes(_) => MetadataType::Bytes,
+        }
+    }
+
+    /// Length (in bytes) of variable-sized values (String & Bytes), 0 otherwise.
+    pub fn byte_len(&self) -> usize {
+        match self {
+            MetadataValue::String(value) => value.len(),
+            MetadataValue::Bytes(value) => value.len(),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub struct Metadata {
     pub key: String,
-    pub value: String,
+    pub value: MetadataValue,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -15,6 +60,33 @@ pub struct MetadataData {
     pub metadata: Vec<Metadata>,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum MetadataType {
+    String,
+    U64,
+    I64,
+    Bool,
+    Pubkey,
+    Bytes,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SchemaField {
+    pub key: String,
+    pub value_type: MetadataType,
+    pub required: bool,
+    pub max_length: Option<u16>, // Only applies to String & Bytes values
+}
+
+impl SchemaField {
+    pub const MAX_SIZE: usize = 4 + MAX_METADATA_KEY_LENGTH + 1 + 1 + 3;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MetadataSchemaData {
+    pub fields: Vec<SchemaField>,
+}
+
 /**
  * Accounts
  */
@@ -24,10 +96,133 @@ pub struct FileMetadata {
     pub file_id: Pubkey,
     pub metadata: Vec<Metadata>,
     pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
 }
 
 impl FileMetadata {
-    pub const MAX_SIZE: usize = 8 + 32 + 4 + (32 * 10);
+    /// Account size required to store the provided metadata (accounts are resized on update).
+    pub fn space(metadata: &[Metadata]) -> usize {
+        8 + 32 + 4 + metadata.iter().map(Self::entry_space).sum::<usize>() + 1 + 32
+    }
+
+    fn entry_space(entry: &Metadata) -> usize {
+        4 + entry.key.len() + entry.value.space()
+    }
+
+    /// Typed metadata stored on the account data, None when the account still has the legacy layout.
+    /// Typed accounts are always sized to their metadata, while legacy ones were allocated with a fixed
+    /// size, so legacy data read with the typed layout never matches the account size.
+    pub fn from_typed(data: &[u8]) -> Option<Self> {
+        let file_metadata = Self::try_deserialize(&mut &data[..]).ok()?;
+        (Self::space(&file_metadata.metadata) == data.len()).then_some(file_metadata)
+    }
+
+    pub fn is_typed(info: &AccountInfo) -> bool {
+        info.try_borrow_data().map(|data| Self::from_typed(&data).is_some()).unwrap_or(false)
+    }
+
+    /// Reads the metadata stored with either layout, legacy string values are returned as String
+    /// values (without payer, legacy metadata was never counted on the File).
+    pub fn load(info: &AccountInfo) -> Result<Self> {
+        if info.owner != &crate::ID {
+            return err!(ErrorCode::AccountOwnedByWrongProgram);
+        }
+        let data = info.try_borrow_data()?;
+        if let Some(file_metadata) = Self::from_typed(&data) {
+            return Ok(file_metadata);
+        }
+        let legacy = LegacyFileMetadata::try_deserialize(&mut &data[..])?;
+        Ok(Self {
+            file_id: legacy.file_id,
+            metadata: legacy.typed_metadata(),
+            bump: legacy.bump,
+            payer: Pubkey::default(),
+        })
+    }
+}
+
+// Keys (and their types) the File metadata must follow, enforced on every metadata update.
+// Keys not included on the schema are accepted with any type.
+#[account]
+pub struct MetadataSchema {
+    pub file_id: Pubkey,
+    pub fields: Vec<SchemaField>,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl MetadataSchema {
+    pub const MAX_SIZE: usize = 8 + 32 + 4 + SchemaField::MAX_SIZE * MAX_METADATA_ENTRIES + 1 + 32;
+
+    /// Schema stored on the provided account (None when the File has no schema).
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        if info.owner != &crate::ID {
+            return err!(ErrorCode::AccountOwnedByWrongProgram);
+        }
+        Ok(Some(Self::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+}
+
+/// Metadata layout before typed values were introduced (values were always strings),
+/// only used to migrate legacy accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyMetadata {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyFileMetadata {
+    pub file_id: Pubkey,
+    pub metadata: Vec<LegacyMetadata>,
+    pub bump: u8,
+}
+
+impl LegacyFileMetadata {
+    /// Legacy string entries converted to typed (String) values.
+    pub fn typed_metadata(&self) -> Vec<Metadata> {
+        self.metadata
+            .iter()
+            .map(|entry| Metadata {
+                key: entry.key.clone(),
+                value: MetadataValue::String(entry.value.clone()),
+            })
+            .collect()
+    }
+}
+
+impl Discriminator for LegacyFileMetadata {
+    const DISCRIMINATOR: [u8; 8] = FileMetadata::DISCRIMINATOR;
+}
+
+impl Owner for LegacyFileMetadata {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+// Nothing is written back on exit, the migration overwrites the account with the new layout.
+impl AccountSerialize for LegacyFileMetadata {}
+
+impl AccountDeserialize for LegacyFileMetadata {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Self::DISCRIMINATOR.len() {
+            return err!(ErrorCode::AccountDiscriminatorNotFound);
+        }
+        if buf[..8] != Self::DISCRIMINATOR {
+            return err!(ErrorCode::AccountDiscriminatorMismatch);
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
 }
 
 /**
@@ -40,4 +235,14 @@ pub struct MetadataUpdated {
     #[index]
     pub file_id: Pubkey,
     pub authority: Pubkey,
+    pub keys: Vec<String>, // Keys added, modified or removed
+}
+
+#[event]
+pub struct MetadataSchemaUpdated {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub authority: Pubkey,
+    pub fields: Vec<SchemaField>,
 }
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+
+/**
+ * Types
+ */
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PermissionsData {
+    pub permissions: Vec<String>,
+}
+
+/**
+ * Accounts
+ */
+
+// Per File list of permissions, where the position of each permission is the bit
+// used on the `Rule.permissions` bitmask (permissions can't be removed to keep bits stable).
+#[account]
+pub struct PermissionRegistry {
+    pub file_id: Pubkey,
+    pub permissions: Vec<String>,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl PermissionRegistry {
+    pub const MAX_PERMISSIONS: usize = 64;
+
+    /// Account size required to store the provided permissions (accounts grow as permissions are added).
+    pub fn space(permissions: &[String]) -> usize {
+        8 + 32 + 4 + permissions.iter().map(|p| 4 + p.len()).sum::<usize>() + 1 + 32
+    }
+
+    /// Bitmask of the provided permission (0 when the permission is not registered).
+    pub fn mask(&self, permission: &str) -> u64 {
+        match self.permissions.iter().position(|p| p == permission) {
+            Some(index) => 1 << index,
+            None => 0,
+        }
+    }
+
+    /// Bitmask including all the registered permissions.
+    pub fn full_mask(&self) -> u64 {
+        match self.permissions.len() {
+            Self::MAX_PERMISSIONS => u64::MAX,
+            len => (1 << len) - 1,
+        }
+    }
+
+    /// Registered permissions included on the provided bitmask.
+    pub fn names(&self, mask: u64) -> Vec<&String> {
+        self.permissions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| mask & (1 << index) != 0)
+            .map(|(_, permission)| permission)
+            .collect()
+    }
+}
+
+/**
+ * Events
+ */
+
+#[event]
+pub struct PermissionsChanged {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub permissions: Vec<String>, // Newly registered permissions
+    pub actor: Pubkey,
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+
+///  RegistryKind (accounts listed on a RegistryPage):
+///     0 => Roles
+///     1 => Rules
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegistryKind {
+    Roles = 0,
+    Rules = 1,
+}
+
+/**
+ * Accounts
+ */
+
+// Per File number of registered Roles and Rules. Entries are kept contiguous, so a list
+// is made of the pages 0..=(count - 1) / RegistryPage::CAPACITY.
+#[account]
+pub struct PolicyRegistry {
+    pub file_id: Pubkey,
+    pub roles: u32,
+    pub rules: u32,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl PolicyRegistry {
+    pub const MAX_SIZE: usize = 8 + 32 + 4 + 4 + 1 + 32;
+
+    /// Registry stored on the provided account (None when the File has no registry yet).
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        if info.owner != &crate::ID {
+            return err!(ErrorCode::AccountOwnedByWrongProgram);
+        }
+        Ok(Some(Self::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
+
+    pub fn count(&self, kind: RegistryKind) -> u32 {
+        match kind {
+            RegistryKind::Roles => self.roles,
+            RegistryKind::Rules => self.rules,
+        }
+    }
+
+    pub fn set_count(&mut self, kind: RegistryKind, count: u32) {
+        match kind {
+            RegistryKind::Roles => self.roles = count,
+            RegistryKind::Rules => self.rules = count,
+        }
+    }
+}
+
+// Page of Role or Rule PDAs of a File.
+#[account]
+pub struct RegistryPage {
+    pub file_id: Pubkey,
+    pub kind: u8,
+    pub page: u32,
+    pub accounts: Vec<Pubkey>,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl RegistryPage {
+    pub const CAPACITY: usize = 32;
+    pub const MAX_SIZE: usize = 8 + 32 + 1 + 4 + 4 + 32 * Self::CAPACITY + 1 + 32;
+}
+
+// Roles assigned to a wallet (only Roles with the "Wallet" address type).
+#[account]
+pub struct WalletRoles {
+    pub file_id: Pubkey,
+    pub wallet: Pubkey,
+    pub roles: Vec<Pubkey>,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl WalletRoles {
+    /// Space required to list the provided number of Roles, the account grows as Roles are added.
+    pub fn space(roles: usize) -> usize {
+        8 + 32 + 32 + 4 + 32 * roles + 1 + 32
+    }
+}
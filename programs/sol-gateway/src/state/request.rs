@@ -0,0 +1,80 @@
+use crate::MAX_JUSTIFICATION_LENGTH;
+use crate::MAX_NAME_LENGTH;
+use anchor_lang::prelude::*;
+
+/**
+ * Types
+ */
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct RoleRequestData {
+    pub role: String,
+    pub justification: Option<String>,
+    pub expires_at: Option<i64>, // Expiration of the requested Role
+}
+
+///  RequestActions (included on RoleRequestChanged events):
+///     Created => The wallet requested the Role
+///     Approved => The Role was assigned and the request closed
+///     Rejected => The request was closed without assigning the Role
+///     Cancelled => The request was closed by the requesting wallet
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum RequestAction {
+    Created,
+    Approved,
+    Rejected,
+    Cancelled,
+}
+
+/**
+ * Accounts
+ */
+
+// Pending request of a Wallet Role, closed (returning the rent to the wallet) once reviewed.
+#[account]
+pub struct RoleRequest {
+    pub file_id: Pubkey,
+    pub wallet: Pubkey,
+    pub role: String,
+    pub justification: Option<String>,
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl RoleRequest {
+    pub const MAX_SIZE: usize = 8 + 32 + 32 + 4 + MAX_NAME_LENGTH + 1 + 4 + MAX_JUSTIFICATION_LENGTH + 9 + 8 + 1 + 32;
+}
+
+/**
+ * Events
+ */
+
+#[event]
+pub struct RoleRequestChanged {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub action: RequestAction,
+    pub request: Pubkey, // RoleRequest PDA
+    pub role_name: String,
+    pub wallet: Pubkey,
+    pub expires_at: Option<i64>,
+    pub actor: Pubkey,
+}
+
+impl RoleRequestChanged {
+    pub fn new(time: i64, action: RequestAction, pda: Pubkey, request: &RoleRequest, actor: Pubkey) -> Self {
+        RoleRequestChanged {
+            time,
+            file_id: request.file_id,
+            action,
+            request: pda,
+            role_name: request.role.clone(),
+            wallet: request.wallet,
+            expires_at: request.expires_at,
+            actor,
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use crate::state::file::ChangeAction;
+use crate::MAX_NAME_LENGTH;
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub enum AddressType {
@@ -26,6 +29,18 @@ pub struct AssignRoleData {
     pub expires_at: Option<i64>,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct UpdateRoleData {
+    pub expires_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+pub struct RoleTemplateData {
+    pub role: String,
+    pub collection: Pubkey,
+    pub duration: i64, // Seconds the claimed Role is valid for
+}
+
 #[account]
 pub struct Role {
     pub file_id: Pubkey,
@@ -34,6 +49,121 @@ pub struct Role {
     pub address_type: AddressType,
     pub expires_at: Option<i64>,
     pub bump: u8,
+    pub registered: bool, // Listed on the File's PolicyRegistry (false for Roles created before the registry)
+    pub payer: Pubkey, // Account that paid the rent (default for Roles created before it was recorded)
+}
+
+impl Role {
+    // SPACE SIZE:
+    // + 8 discriminator
+    // + 32 file_id (Pubkey)
+    // + 1 + 32 address Option<Pubkey>
+    // + 4 + 64 role (string)
+    // + 1 address_type (enum)
+    // + 1 + 8 expires_at Option<i64>
+    // + 1 bump
+    // + 1 registered (bool)
+    // + 32 payer (Pubkey)
+    // total = 8 + 32 + 1 + 32 + 4 + 64 + 1 + 1 + 8 + 1 + 1 + 32 = 185
+    pub const MAX_SIZE: usize = 8 + 32 + 1 + 32 + 4 + MAX_NAME_LENGTH + 1 + 1 + 8 + 1 + 1 + 32;
+
+    /// Account receiving the rent when the Role is closed (the File authority when the payer is unknown).
+    pub fn rent_collector(&self, authority: &Pubkey) -> Pubkey {
+        if self.payer == Pubkey::default() {
+            *authority
+        } else {
+            self.payer
+        }
+    }
+}
+
+/// Role layout before the registry flag and payer were introduced, only used to migrate or register
+/// legacy accounts. Shares the discriminator with `Role`, as both are stored under the same account type.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyRole {
+    pub file_id: Pubkey,
+    pub address: Option<Pubkey>,
+    pub role: String,
+    pub address_type: AddressType,
+    pub expires_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl LegacyRole {
+    /// Registered Role with the same fields (its payer is unknown).
+    pub fn to_role(&self) -> Role {
+        Role {
+            file_id: self.file_id,
+            address: self.address,
+            role: self.role.clone(),
+            address_type: self.address_type.clone(),
+            expires_at: self.expires_at,
+            bump: self.bump,
+            registered: true,
+            payer: Pubkey::default(),
+        }
+    }
+}
+
+impl Discriminator for LegacyRole {
+    const DISCRIMINATOR: [u8; 8] = Role::DISCRIMINATOR;
+}
+
+impl Owner for LegacyRole {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl AccountSerialize for LegacyRole {}
+
+impl AccountDeserialize for LegacyRole {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Self::DISCRIMINATOR.len() {
+            return err!(ErrorCode::AccountDiscriminatorNotFound);
+        }
+        if buf[..8] != Self::DISCRIMINATOR {
+            return err!(ErrorCode::AccountDiscriminatorMismatch);
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
+// Role granted to the holders of a verified collection NFT, so they can claim a personal Wallet Role.
+#[account]
+pub struct RoleTemplate {
+    pub file_id: Pubkey,
+    pub role: String,
+    pub collection: Pubkey,
+    pub duration: i64,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl RoleTemplate {
+    pub const MAX_SIZE: usize = 8 + 32 + 4 + MAX_NAME_LENGTH + 32 + 8 + 1 + 32;
+}
+
+// Token used to claim a RoleTemplate, so it can't claim the Role again (from any wallet) until it expires.
+#[account]
+pub struct RoleClaim {
+    pub file_id: Pubkey,
+    pub template: Pubkey,
+    pub mint: Pubkey,
+    pub wallet: Pubkey, // Last wallet claiming the Role with the token
+    pub expires_at: i64,
+    pub bump: u8,
+    pub payer: Pubkey, // Account that paid the rent, refunded when the File is deleted
+}
+
+impl RoleClaim {
+    pub const MAX_SIZE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 1 + 32;
 }
 
 #[event]
@@ -41,4 +171,40 @@ pub struct RolesChanged {
     pub time: i64,
     #[index]
     pub file_id: Pubkey,
+    pub action: ChangeAction,
+    pub role: Pubkey, // Role PDA
+    pub role_name: String,
+    pub address: Option<Pubkey>,
+    pub address_type: AddressType,
+    pub expires_at: Option<i64>,
+    pub actor: Pubkey,
+}
+
+impl RolesChanged {
+    pub fn new(time: i64, action: ChangeAction, pda: Pubkey, role: &Role, actor: Pubkey) -> Self {
+        RolesChanged {
+            time,
+            file_id: role.file_id,
+            action,
+            role: pda,
+            role_name: role.role.clone(),
+            address: role.address,
+            address_type: role.address_type.clone(),
+            expires_at: role.expires_at,
+            actor,
+        }
+    }
+}
+
+#[event]
+pub struct RoleTemplateChanged {
+    pub time: i64,
+    #[index]
+    pub file_id: Pubkey,
+    pub action: ChangeAction,
+    pub template: Pubkey, // RoleTemplate PDA
+    pub role_name: String,
+    pub collection: Pubkey,
+    pub duration: i64,
+    pub actor: Pubkey,
 }
@@ -1,4 +1,7 @@
+use crate::state::file::ChangeAction;
+use crate::MAX_NAME_LENGTH;
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Debug)]
 pub struct RuleData {
@@ -6,6 +9,12 @@ pub struct RuleData {
     pub role: String,
     pub resource: String,
     pub permission: String,
+    pub permissions: u64, // Bitmask over the File's PermissionRegistry (Permission must be empty when used)
+    pub expires_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Debug)]
+pub struct UpdateRuleData {
     pub expires_at: Option<i64>,
 }
 
@@ -17,6 +26,14 @@ pub struct RuleData {
 ///    4 => AddRuleResourcePerm (White list of resources and permissions that can be created by certain role)
 ///    5 => DeleteRuleNSRole (White list of namespaces and roles that can be deleted by certain role)
 ///    6 => DeleteRuleResourcePerm (White list of resources and permissions that can be deleted by certain role)
+///    7 => UpdateAssignRole (White list of roles that can be updated by certain role)
+///    8 => UpdateRuleNSRole (White list of namespaces and roles that can be updated by certain role)
+///    9 => UpdateRuleResourcePerm (White list of resources and permissions that can be updated by certain role)
+///   10 => UpdateMetadata (Metadata keys that can be updated by certain role, resource: "metadata", permission: key)
+///   11 => UpdateFile (File fields that can be updated by certain role, resource: "file", permission: "update")
+///   12 => DeleteFile (Files that can be deleted by certain role, resource: "file", permission: "delete")
+///   13 => UpdateCache (Cache timestamps that can be updated by certain role, resource: "cache", permission: "roles" or "rules")
+///   14 => ReviewRequest (Role requests that can be approved or rejected by certain role, resource: "request", permission: role)
 #[repr(u8)]
 pub enum Namespaces {
     Rule = 0,
@@ -26,6 +43,14 @@ pub enum Namespaces {
     AddRuleResourcePerm = 4,
     DeleteRuleNSRole = 5,
     DeleteRuleResourcePerm = 6,
+    UpdateAssignRole = 7,
+    UpdateRuleNSRole = 8,
+    UpdateRuleResourcePerm = 9,
+    UpdateMetadata = 10,
+    UpdateFile = 11,
+    DeleteFile = 12,
+    UpdateCache = 13,
+    ReviewRequest = 14,
 }
 
 #[account]
@@ -35,13 +60,170 @@ pub struct Rule {
     pub role: String,
     pub resource: String,
     pub permission: String,
+    pub permissions: u64,
+    pub expires_at: Option<i64>,
+    pub bump: u8,
+    pub registered: bool, // Listed on the File's PolicyRegistry (false for Rules created before the registry)
+    pub payer: Pubkey, // Account that paid the rent (default for Rules created before it was recorded)
+}
+
+impl Rule {
+    // SPACE SIZE:
+    // + 8 discriminator
+    // + 32 file_id (Pubkey)
+    // + 1 namespace (u8)
+    // + 4 + 64 role (string)
+    // + 4 + 64 resource (string)
+    // + 4 + 64 permission (string)
+    // + 8 permissions (u64)
+    // + 1 + 8 expires_at Option<i64>
+    // + 1 bump
+    // + 1 registered (bool)
+    // + 32 payer (Pubkey)
+    // total = 8 + 32 + 1 + 4 + 64 + 4 + 64 + 4 + 64 + 8 + 1 + 8 + 1 + 1 + 32 = 296
+    pub const MAX_SIZE: usize = 8 + 32 + 1 + (4 + MAX_NAME_LENGTH) * 3 + 8 + 1 + 8 + 1 + 1 + 32;
+
+    /// Rules granting a set of permissions (bitmask) instead of a single one.
+    pub fn is_permission_set(&self) -> bool {
+        self.permissions != 0
+    }
+}
+
+/// Rule layout before permission sets were introduced, only used to migrate legacy accounts.
+/// Shares the discriminator with `Rule`, as both are stored under the same account type.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyRule {
+    pub file_id: Pubkey,
+    pub namespace: u8,
+    pub role: String,
+    pub resource: String,
+    pub permission: String,
+    pub expires_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl Discriminator for LegacyRule {
+    const DISCRIMINATOR: [u8; 8] = Rule::DISCRIMINATOR;
+}
+
+impl Owner for LegacyRule {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl AccountSerialize for LegacyRule {}
+
+impl AccountDeserialize for LegacyRule {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Self::DISCRIMINATOR.len() {
+            return err!(ErrorCode::AccountDiscriminatorNotFound);
+        }
+        if buf[..8] != Self::DISCRIMINATOR {
+            return err!(ErrorCode::AccountDiscriminatorMismatch);
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
+/// Rule layout with permission sets but before the registry flag and payer were introduced, only used
+/// to register those accounts. Shares the discriminator with `Rule`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UnregisteredRule {
+    pub file_id: Pubkey,
+    pub namespace: u8,
+    pub role: String,
+    pub resource: String,
+    pub permission: String,
+    pub permissions: u64,
     pub expires_at: Option<i64>,
     pub bump: u8,
 }
 
+impl UnregisteredRule {
+    /// Registered Rule with the same fields (its payer is unknown).
+    pub fn to_rule(&self) -> Rule {
+        Rule {
+            file_id: self.file_id,
+            namespace: self.namespace,
+            role: self.role.clone(),
+            resource: self.resource.clone(),
+            permission: self.permission.clone(),
+            permissions: self.permissions,
+            expires_at: self.expires_at,
+            bump: self.bump,
+            registered: true,
+            payer: Pubkey::default(),
+        }
+    }
+}
+
+impl Discriminator for UnregisteredRule {
+    const DISCRIMINATOR: [u8; 8] = Rule::DISCRIMINATOR;
+}
+
+impl Owner for UnregisteredRule {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl AccountSerialize for UnregisteredRule {}
+
+impl AccountDeserialize for UnregisteredRule {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Self::DISCRIMINATOR.len() {
+            return err!(ErrorCode::AccountDiscriminatorNotFound);
+        }
+        if buf[..8] != Self::DISCRIMINATOR {
+            return err!(ErrorCode::AccountDiscriminatorMismatch);
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
 #[event]
 pub struct RulesChanged {
     pub time: i64,
     #[index]
     pub file_id: Pubkey,
+    pub action: ChangeAction,
+    pub rule: Pubkey, // Rule PDA
+    pub namespace: u8,
+    pub role: String,
+    pub resource: String,
+    pub permission: String,
+    pub permissions: u64,
+    pub expires_at: Option<i64>,
+    pub actor: Pubkey,
+}
+
+impl RulesChanged {
+    pub fn new(time: i64, action: ChangeAction, pda: Pubkey, rule: &Rule, actor: Pubkey) -> Self {
+        RulesChanged {
+            time,
+            file_id: rule.file_id,
+            action,
+            rule: pda,
+            namespace: rule.namespace,
+            role: rule.role.clone(),
+            resource: rule.resource.clone(),
+            permission: rule.permission.clone(),
+            permissions: rule.permissions,
+            expires_at: rule.expires_at,
+            actor,
+        }
+    }
 }
@@ -1,13 +1,22 @@
 pub use file::*;
+pub use metadata::*;
+pub use policy::*;
+pub use registry::*;
 pub use roles::*;
 pub use rules::*;
+pub use seeds::*;
 pub use solana::*;
 pub use strings::*;
 pub use time::*;
 
+pub mod events;
 pub mod file;
+pub mod metadata;
+pub mod policy;
+pub mod registry;
 pub mod roles;
 pub mod rules;
+pub mod seeds;
 pub mod solana;
 pub mod strings;
 pub mod time;
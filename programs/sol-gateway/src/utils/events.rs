@@ -0,0 +1,18 @@
+/// Emits an event through a self-CPI (Anchor's event-CPI), so it is stored as inner instruction
+/// data instead of program logs which RPC nodes might truncate.
+/// Events are emitted as program logs when the "log-events" feature is enabled.
+///
+/// Requires the accounts struct to be annotated with:
+///     #[cfg_attr(not(feature = "log-events"), event_cpi)]
+#[macro_export]
+macro_rules! emit_event {
+    ($ctx:expr, $event:expr) => {{
+        #[cfg(feature = "log-events")]
+        anchor_lang::prelude::emit!($event);
+        #[cfg(not(feature = "log-events"))]
+        {
+            let ctx = &$ctx;
+            anchor_lang::prelude::emit_cpi!($event);
+        }
+    }};
+}
@@ -1,4 +1,5 @@
 use crate::Errors;
+use crate::state::CacheUpdated;
 use crate::File;
 use crate::FEE;
 use crate::PROGRAM_AUTHORITY;
@@ -8,6 +9,25 @@ pub fn allowed_authority(authority: &Pubkey, file_authority: &Pubkey) -> bool {
     return authority.key() == file_authority.key() || authority.key() == PROGRAM_AUTHORITY.key();
 }
 
+/// The File recovery key (when defined) can also manage the File authority.
+pub fn allowed_recovery(signer: &Pubkey, file: &File) -> bool {
+    file.recovery == Some(*signer)
+}
+
+/// Frozen Files only allow the namespaces set when freezing them.
+pub fn frozen(file: &File, namespace: u8) -> bool {
+    file.frozen_at.is_some() && !file.frozen_namespaces.contains(&namespace)
+}
+
+/// Parses the cache type received by "update_cache" (0 => Roles, 1 => Rules).
+pub fn parse_cache_updated(value: u8) -> Result<CacheUpdated> {
+    match value {
+        v if v == CacheUpdated::Roles as u8 => Ok(CacheUpdated::Roles),
+        v if v == CacheUpdated::Rules as u8 => Ok(CacheUpdated::Rules),
+        _ => err!(Errors::InvalidCacheUpdated),
+    }
+}
+
 /// Gets the default fee applied to each "Allowed" request
 pub fn get_fee(file: &File) -> u64 {
     if FEE.is_none() {
@@ -80,10 +100,70 @@ mod tests {
             checksum: "test".to_string(),
             account_type: AccountTypes::Basic as u8,
             expires_at: 0,
+            audit: false,
+            policy_hash: [0; 32],
+            roles_count: 0,
+            rules_count: 0,
+            max_roles: None,
+            max_rules: None,
+            deleted_at: None,
+            frozen_at: None,
+            frozen_namespaces: vec![],
+            children_count: 0,
+            migrating: false,
         };
         assert_eq!(get_fee(&file), if FEE.is_some() { FEE.unwrap() } else { 0 });
         file.fee = Some(10);
         assert_eq!(get_fee(&file), 10);
+        // Recovery key
+        let recovery = pubkey!("Ft9dAWwsFV8wFKmdgCJAe21ZnnqtXBBAdVB3cjUyRMY9");
+        assert!(!allowed_recovery(&recovery, &file));
+        file.recovery = Some(recovery);
+        assert!(allowed_recovery(&recovery, &file));
+        assert!(!allowed_recovery(&file.authority, &file));
+        // Cache timestamps
+        file.touch_cache(CacheUpdated::Rules, 100);
+        assert_eq!((file.roles_updated_at, file.rules_updated_at), (0, 100));
+        file.touch_cache(CacheUpdated::Roles, 200);
+        assert_eq!((file.roles_updated_at, file.rules_updated_at), (200, 100));
+        // Counters & limits
+        file.max_rules = Some(1);
+        assert_eq!(file.add_rule(), Ok(()));
+        assert_eq!(file.add_rule(), err!(Errors::TooManyRules));
+        assert_eq!(file.add_role(), Ok(()));
+        assert_eq!(file.add_role(), Ok(()));
+        assert_eq!((file.roles_count, file.rules_count), (2, 1));
+        file.remove_rule();
+        file.remove_rule();
+        assert_eq!(file.rules_count, 0);
+        // Other File accounts are counted once, when their payer is recorded
+        let payer = pubkey!("Ft9dAWwsFV8wFKmdgCJAe21ZnnqtXBBAdVB3cjUyRMY9");
+        let mut child_payer = Pubkey::default();
+        file.add_child(&mut child_payer, payer);
+        file.add_child(&mut child_payer, file.authority);
+        assert_eq!((file.children_count, child_payer), (1, payer));
+        file.remove_role();
+        file.remove_role();
+        assert!(!file.is_empty());
+        file.remove_child();
+        assert!(file.is_empty());
+        // Migrating Files may have legacy Roles and Rules not counted yet
+        file.migrating = true;
+        assert!(!file.is_empty());
+        file.migrating = false;
+        // Freeze
+        assert!(!frozen(&file, 1));
+        file.frozen_at = Some(300);
+        file.frozen_namespaces = vec![2];
+        assert!(frozen(&file, 1));
+        assert!(!frozen(&file, 2));
+    }
+
+    #[test]
+    fn test_parse_cache_updated() {
+        assert_eq!(parse_cache_updated(0), Ok(CacheUpdated::Roles));
+        assert_eq!(parse_cache_updated(1), Ok(CacheUpdated::Rules));
+        assert_eq!(parse_cache_updated(2), err!(Errors::InvalidCacheUpdated));
     }
 
     #[test]
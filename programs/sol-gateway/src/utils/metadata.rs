@@ -0,0 +1,282 @@
+use crate::state::metadata::{Metadata, SchemaField};
+use crate::{Errors, MAX_METADATA_ENTRIES, MAX_METADATA_KEY_LENGTH, MAX_METADATA_VALUE_LENGTH};
+use anchor_lang::prelude::*;
+
+/// Validates metadata entries: key & value lengths, number of entries and duplicated keys.
+pub fn validate_metadata(metadata: &[Metadata]) -> Result<()> {
+    if metadata.len() > MAX_METADATA_ENTRIES {
+        return err!(Errors::TooManyMetadataEntries);
+    }
+    for (index, entry) in metadata.iter().enumerate() {
+        if entry.key.is_empty() || entry.key.len() > MAX_METADATA_KEY_LENGTH {
+            return err!(Errors::InvalidMetadataKey);
+        }
+        if entry.value.byte_len() > MAX_METADATA_VALUE_LENGTH {
+            return err!(Errors::MetadataValueTooLong);
+        }
+        if metadata[..index].iter().any(|m| m.key == entry.key) {
+            return err!(Errors::DuplicatedMetadataKey);
+        }
+    }
+    Ok(())
+}
+
+/// Validates schema fields: key lengths, max lengths, number of fields and duplicated keys.
+pub fn validate_schema_fields(fields: &[SchemaField]) -> Result<()> {
+    if fields.len() > MAX_METADATA_ENTRIES {
+        return err!(Errors::TooManyMetadataEntries);
+    }
+    for (index, field) in fields.iter().enumerate() {
+        if field.key.is_empty() || field.key.len() > MAX_METADATA_KEY_LENGTH {
+            return err!(Errors::InvalidMetadataKey);
+        }
+        if field.max_length.unwrap_or(0) as usize > MAX_METADATA_VALUE_LENGTH {
+            return err!(Errors::InvalidSchemaMaxLength);
+        }
+        if fields[..index].iter().any(|f| f.key == field.key) {
+            return err!(Errors::DuplicatedMetadataKey);
+        }
+    }
+    Ok(())
+}
+
+/// Validates the metadata follows the schema: required keys, value types and max lengths.
+pub fn validate_schema(metadata: &[Metadata], fields: &[SchemaField]) -> Result<()> {
+    for field in fields {
+        match metadata.iter().find(|entry| entry.key == field.key) {
+            None if field.required => return err!(Errors::MissingMetadataKey),
+            None => {}
+            Some(entry) => {
+                if entry.value.value_type() != field.value_type {
+                    return err!(Errors::InvalidMetadataType);
+                }
+                if let Some(max_length) = field.max_length {
+                    if entry.value.byte_len() > max_length as usize {
+                        return err!(Errors::MetadataValueTooLong);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keys added, modified or removed between both versions of the metadata.
+pub fn changed_metadata_keys(old: &[Metadata], new: &[Metadata]) -> Vec<String> {
+    let mut keys: Vec<String> = new
+        .iter()
+        .filter(|entry| !old.contains(entry))
+        .map(|entry| entry.key.clone())
+        .collect();
+    for entry in old {
+        if !new.iter().any(|m| m.key == entry.key) {
+            keys.push(entry.key.clone());
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::metadata::{MetadataType, MetadataValue};
+
+    fn entry(key: &str, value: &str) -> Metadata {
+        Metadata {
+            key: key.to_string(),
+            value: MetadataValue::String(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate_metadata() {
+        assert_eq!(validate_metadata(&[]), Ok(()));
+        assert_eq!(
+            validate_metadata(&[entry("author", "Jane Doe"), entry("version", "")]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_metadata(&[entry("", "Jane Doe")]),
+            err!(Errors::InvalidMetadataKey)
+        );
+        assert_eq!(
+            validate_metadata(&[entry(&"k".repeat(MAX_METADATA_KEY_LENGTH + 1), "")]),
+            err!(Errors::InvalidMetadataKey)
+        );
+        assert_eq!(
+            validate_metadata(&[entry("provenance", &"v".repeat(MAX_METADATA_VALUE_LENGTH))]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_metadata(&[entry("provenance", &"v".repeat(MAX_METADATA_VALUE_LENGTH + 1))]),
+            err!(Errors::MetadataValueTooLong)
+        );
+        assert_eq!(
+            validate_metadata(&[Metadata {
+                key: "checksum".to_string(),
+                value: MetadataValue::Bytes(vec![0; MAX_METADATA_VALUE_LENGTH + 1]),
+            }]),
+            err!(Errors::MetadataValueTooLong)
+        );
+        assert_eq!(
+            validate_metadata(&[
+                Metadata {
+                    key: "max_downloads".to_string(),
+                    value: MetadataValue::U64(u64::MAX),
+                },
+                Metadata {
+                    key: "public".to_string(),
+                    value: MetadataValue::Bool(true),
+                },
+                Metadata {
+                    key: "publisher".to_string(),
+                    value: MetadataValue::Pubkey(Pubkey::default()),
+                },
+            ]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_metadata(&[entry("author", "Jane"), entry("author", "John")]),
+            err!(Errors::DuplicatedMetadataKey)
+        );
+        let entries: Vec<Metadata> = (0..=MAX_METADATA_ENTRIES)
+            .map(|i| entry(&i.to_string(), ""))
+            .collect();
+        assert_eq!(
+            validate_metadata(&entries[..MAX_METADATA_ENTRIES]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_metadata(&entries),
+            err!(Errors::TooManyMetadataEntries)
+        );
+    }
+
+    #[test]
+    fn test_changed_metadata_keys() {
+        let old = [entry("author", "Jane Doe"), entry("version", "1.0")];
+        assert_eq!(changed_metadata_keys(&old, &old), Vec::<String>::new());
+        assert_eq!(
+            changed_metadata_keys(&old, &[entry("author", "Jane Doe"), entry("version", "2.0")]),
+            vec!["version"]
+        );
+        assert_eq!(
+            changed_metadata_keys(&old, &[entry("license", "MIT"), entry("author", "Jane Doe")]),
+            vec!["license", "version"]
+        );
+        assert_eq!(changed_metadata_keys(&[], &old), vec!["author", "version"]);
+        assert_eq!(changed_metadata_keys(&old, &[]), vec!["author", "version"]);
+    }
+
+    fn field(key: &str, value_type: MetadataType, required: bool, max_length: Option<u16>) -> SchemaField {
+        SchemaField {
+            key: key.to_string(),
+            value_type,
+            required,
+            max_length,
+        }
+    }
+
+    #[test]
+    fn test_validate_schema_fields() {
+        assert_eq!(
+            validate_schema_fields(&[
+                field("license", MetadataType::String, true, Some(16)),
+                field("max_downloads", MetadataType::U64, false, None),
+            ]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_schema_fields(&[field("", MetadataType::String, true, None)]),
+            err!(Errors::InvalidMetadataKey)
+        );
+        assert_eq!(
+            validate_schema_fields(&[field("license", MetadataType::String, true, Some(257))]),
+            err!(Errors::InvalidSchemaMaxLength)
+        );
+        assert_eq!(
+            validate_schema_fields(&[
+                field("license", MetadataType::String, true, None),
+                field("license", MetadataType::Bytes, false, None),
+            ]),
+            err!(Errors::DuplicatedMetadataKey)
+        );
+    }
+
+    #[test]
+    fn test_validate_schema() {
+        let fields = [
+            field("license", MetadataType::String, true, Some(8)),
+            field("max_downloads", MetadataType::U64, false, None),
+        ];
+        assert_eq!(validate_schema(&[entry("license", "MIT")], &fields), Ok(()));
+        assert_eq!(
+            validate_schema(&[entry("license", "MIT"), entry("author", "Jane Doe")], &fields),
+            Ok(())
+        );
+        assert_eq!(
+            validate_schema(&[entry("author", "Jane Doe")], &fields),
+            err!(Errors::MissingMetadataKey)
+        );
+        assert_eq!(
+            validate_schema(&[entry("license", "Apache-2.0")], &fields),
+            err!(Errors::MetadataValueTooLong)
+        );
+        assert_eq!(
+            validate_schema(&[entry("license", "MIT"), entry("max_downloads", "100")], &fields),
+            err!(Errors::InvalidMetadataType)
+        );
+        assert_eq!(
+            validate_schema(
+                &[
+                    entry("license", "MIT"),
+                    Metadata {
+                        key: "max_downloads".to_string(),
+                        value: MetadataValue::U64(100),
+                    },
+                ],
+                &fields
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_metadata_layouts() {
+        use crate::state::metadata::{FileMetadata, LegacyFileMetadata, LegacyMetadata};
+        use anchor_lang::Discriminator;
+
+        // Legacy accounts were allocated with a fixed size (364 bytes)
+        let legacy_data = |metadata: Vec<LegacyMetadata>| {
+            let mut data = FileMetadata::DISCRIMINATOR.to_vec();
+            LegacyFileMetadata { file_id: Pubkey::new_unique(), metadata, bump: 255 }
+                .serialize(&mut data)
+                .unwrap();
+            data.resize(8 + 32 + 4 + 32 * 10, 0);
+            data
+        };
+        for metadata in [
+            vec![],
+            vec![LegacyMetadata { key: "author".to_string(), value: "abc".to_string() }],
+        ] {
+            let data = legacy_data(metadata.clone());
+            assert!(FileMetadata::from_typed(&data).is_none());
+            let legacy = LegacyFileMetadata::try_deserialize(&mut &data[..]).unwrap();
+            assert_eq!(legacy.typed_metadata().len(), metadata.len());
+        }
+
+        // Typed accounts are sized to their metadata
+        let file_metadata = FileMetadata {
+            file_id: Pubkey::new_unique(),
+            metadata: vec![entry("author", "Jane Doe")],
+            bump: 255,
+            payer: Pubkey::new_unique(),
+        };
+        let mut data = Vec::new();
+        file_metadata.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), FileMetadata::space(&file_metadata.metadata));
+        let typed = FileMetadata::from_typed(&data).unwrap();
+        assert_eq!(typed.metadata, file_metadata.metadata);
+        assert_eq!(typed.payer, file_metadata.payer);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::state::file::ChangeAction;
+use crate::state::role::Role;
+use crate::state::rule::Rule;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+/// Roles and Rules included on the File policy hash.
+pub trait PolicyAccount {
+    /// Hash of the policy fields only, bookkeeping fields (bump, registered, payer...) are not part of the policy.
+    fn policy_leaf(&self) -> Result<[u8; 32]>;
+}
+
+impl PolicyAccount for Role {
+    // SHA-256("role" || role || address || address_type || expires_at)
+    fn policy_leaf(&self) -> Result<[u8; 32]> {
+        let mut data = Vec::new();
+        self.role.serialize(&mut data)?;
+        self.address.serialize(&mut data)?;
+        self.address_type.serialize(&mut data)?;
+        self.expires_at.serialize(&mut data)?;
+        Ok(hashv(&[b"role".as_ref(), &data]).to_bytes())
+    }
+}
+
+impl PolicyAccount for Rule {
+    // SHA-256("rule" || namespace || role || resource || permission || permissions || expires_at)
+    fn policy_leaf(&self) -> Result<[u8; 32]> {
+        let mut data = Vec::new();
+        self.namespace.serialize(&mut data)?;
+        self.role.serialize(&mut data)?;
+        self.resource.serialize(&mut data)?;
+        self.permission.serialize(&mut data)?;
+        self.permissions.serialize(&mut data)?;
+        self.expires_at.serialize(&mut data)?;
+        Ok(hashv(&[b"rule".as_ref(), &data]).to_bytes())
+    }
+}
+
+/// Chains a Role or Rule change to the policy hash: SHA-256(policy_hash || action || leaf).
+/// The hash commits to the whole (ordered) history of changes, so it can't be matched by
+/// a different set of Roles and Rules without breaking SHA-256.
+pub fn chain_policy_hash(policy_hash: &[u8; 32], action: ChangeAction, leaf: &[u8; 32]) -> [u8; 32] {
+    hashv(&[policy_hash.as_ref(), &[action as u8], leaf.as_ref()]).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey;
+
+    #[test]
+    fn test_policy_hash() {
+        let address1 = pubkey!("6kJuLfs8BrKwxy28FCmcPfp4d5stv4Sr6YgV15A6s7FK");
+        let mut rule = Rule {
+            file_id: address1,
+            namespace: 0,
+            role: "Admin".to_string(),
+            resource: "*".to_string(),
+            permission: "*".to_string(),
+            permissions: 0,
+            expires_at: None,
+            bump: 255,
+            registered: false,
+            payer: Pubkey::default(),
+        };
+        let leaf1 = rule.policy_leaf().unwrap();
+        // Bookkeeping fields don't change the leaf
+        rule.registered = true;
+        rule.payer = address1;
+        rule.bump = 254;
+        assert_eq!(leaf1, rule.policy_leaf().unwrap());
+        // Policy fields do
+        rule.expires_at = Some(1);
+        let leaf2 = rule.policy_leaf().unwrap();
+        assert_ne!(leaf1, leaf2);
+        // Order and action are part of the hash
+        let hash_a = chain_policy_hash(&chain_policy_hash(&[0; 32], ChangeAction::Created, &leaf1), ChangeAction::Created, &leaf2);
+        let hash_b = chain_policy_hash(&chain_policy_hash(&[0; 32], ChangeAction::Created, &leaf2), ChangeAction::Created, &leaf1);
+        assert_ne!(hash_a, hash_b);
+        assert_ne!(
+            chain_policy_hash(&[0; 32], ChangeAction::Created, &leaf1),
+            chain_policy_hash(&[0; 32], ChangeAction::Deleted, &leaf1)
+        );
+        // Roles and Rules never share a leaf
+        let role = Role {
+            file_id: address1,
+            address: None,
+            role: "Admin".to_string(),
+            address_type: crate::state::role::AddressType::Collection,
+            expires_at: None,
+            bump: 255,
+            registered: true,
+            payer: Pubkey::default(),
+        };
+        assert_ne!(role.policy_leaf().unwrap(), leaf1);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::state::file::File;
+use crate::state::registry::*;
+use crate::utils::grow_account;
+use crate::Errors;
+use anchor_lang::prelude::*;
+
+/// Page where the next entry is added (entries are contiguous across pages).
+pub fn registry_page_index(count: u32) -> u32 {
+    count / RegistryPage::CAPACITY as u32
+}
+
+/// Page holding the last entry, which fills the gap left by removed entries.
+pub fn registry_last_page_index(count: u32) -> u32 {
+    registry_page_index(count.saturating_sub(1))
+}
+
+/// Appends the account to the last page of the registry.
+pub fn registry_insert(
+    registry: &mut PolicyRegistry,
+    kind: RegistryKind,
+    page: &mut RegistryPage,
+    account: Pubkey,
+) -> Result<()> {
+    let count = registry.count(kind);
+    if page.kind != kind as u8 || page.page != registry_page_index(count) {
+        return err!(Errors::InvalidRegistryPage);
+    }
+    page.accounts.push(account);
+    registry.set_count(kind, count + 1);
+    Ok(())
+}
+
+/// Removes the account from its page, moving the last entry of the registry into its place.
+/// `last_page` is only required when the account is not on the last page.
+pub fn registry_remove(
+    registry: &mut PolicyRegistry,
+    kind: RegistryKind,
+    page: &mut RegistryPage,
+    last_page: Option<&mut RegistryPage>,
+    account: &Pubkey,
+) -> Result<()> {
+    let count = registry.count(kind);
+    if page.kind != kind as u8 {
+        return err!(Errors::InvalidRegistryPage);
+    }
+    let position = match page.accounts.iter().position(|a| a == account) {
+        Some(position) => position,
+        None => return err!(Errors::RegistryEntryNotFound),
+    };
+    if page.page == registry_last_page_index(count) {
+        if last_page.is_some() {
+            return err!(Errors::InvalidRegistryPage);
+        }
+        page.accounts.swap_remove(position);
+    } else {
+        let last_page = match last_page {
+            Some(last_page) => last_page,
+            None => return err!(Errors::MissingRegistryAccount),
+        };
+        if last_page.kind != kind as u8 || last_page.page != registry_last_page_index(count) {
+            return err!(Errors::InvalidRegistryPage);
+        }
+        match last_page.accounts.pop() {
+            Some(last) => page.accounts[position] = last,
+            None => return err!(Errors::InvalidRegistryPage),
+        }
+    }
+    registry.set_count(kind, count - 1);
+    Ok(())
+}
+
+/// Adds the account to the File registry, initializing the registry and page when they were just created
+/// (counted as File accounts paid by `payer`).
+#[allow(clippy::too_many_arguments)]
+pub fn register(
+    registry: &mut PolicyRegistry,
+    registry_bump: u8,
+    page: &mut RegistryPage,
+    page_bump: u8,
+    file: &mut File,
+    payer: Pubkey,
+    kind: RegistryKind,
+    account: Pubkey,
+) -> Result<()> {
+    file.add_child(&mut registry.payer, payer);
+    file.add_child(&mut page.payer, payer);
+    registry.file_id = file.id;
+    registry.bump = registry_bump;
+    page.file_id = file.id;
+    page.kind = kind as u8;
+    page.page = registry_page_index(registry.count(kind));
+    page.bump = page_bump;
+    registry_insert(registry, kind, page, account)
+}
+
+/// Removes a registered account from the File registry. Accounts created before the registry
+/// are not listed until they are registered, so they must be skipped (see `Role::registered`).
+pub fn unregister(
+    registry_info: &AccountInfo,
+    kind: RegistryKind,
+    page: Option<&mut RegistryPage>,
+    last_page: Option<&mut RegistryPage>,
+    account: &Pubkey,
+) -> Result<()> {
+    let mut registry = match PolicyRegistry::load(registry_info)? {
+        Some(registry) => registry,
+        None => return err!(Errors::MissingRegistryAccount),
+    };
+    let page = match page {
+        Some(page) => page,
+        None => return err!(Errors::MissingRegistryAccount),
+    };
+    registry_remove(&mut registry, kind, page, last_page, account)?;
+    let mut data = registry_info.try_borrow_mut_data()?;
+    registry.try_serialize(&mut &mut data[..])
+}
+
+/// Adds the Role to the wallet roles list, growing the account (paid by `payer`) when it's full.
+pub fn wallet_roles_insert<'info>(
+    wallet_roles: &mut Account<'info, WalletRoles>,
+    role: Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    grow_account(
+        &wallet_roles.to_account_info(),
+        WalletRoles::space(wallet_roles.roles.len() + 1),
+        payer,
+        system_program,
+    )?;
+    wallet_roles.roles.push(role);
+    Ok(())
+}
+
+/// Removes the Role from the wallet roles list.
+pub fn wallet_roles_remove(wallet_roles: &mut WalletRoles, role: &Pubkey) -> Result<()> {
+    match wallet_roles.roles.iter().position(|r| r == role) {
+        Some(position) => {
+            wallet_roles.roles.swap_remove(position);
+            Ok(())
+        }
+        None => err!(Errors::RegistryEntryNotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_page(kind: RegistryKind, page: u32) -> RegistryPage {
+        RegistryPage {
+            file_id: Pubkey::default(),
+            kind: kind as u8,
+            page,
+            accounts: vec![],
+            bump: 0,
+            payer: Pubkey::default(),
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = PolicyRegistry {
+            file_id: Pubkey::default(),
+            roles: 0,
+            rules: 0,
+            bump: 0,
+            payer: Pubkey::default(),
+        };
+        let mut page0 = new_page(RegistryKind::Rules, 0);
+        let mut page1 = new_page(RegistryKind::Rules, 1);
+        let accounts: Vec<Pubkey> = (0..RegistryPage::CAPACITY + 2).map(|_| Pubkey::new_unique()).collect();
+        // Roles pages can't hold Rules
+        assert_eq!(
+            registry_insert(&mut registry, RegistryKind::Rules, &mut new_page(RegistryKind::Roles, 0), accounts[0]),
+            err!(Errors::InvalidRegistryPage)
+        );
+        for account in &accounts[..RegistryPage::CAPACITY] {
+            registry_insert(&mut registry, RegistryKind::Rules, &mut page0, *account).unwrap();
+        }
+        // First page is full
+        assert_eq!(
+            registry_insert(&mut registry, RegistryKind::Rules, &mut page0, accounts[RegistryPage::CAPACITY]),
+            err!(Errors::InvalidRegistryPage)
+        );
+        for account in &accounts[RegistryPage::CAPACITY..] {
+            registry_insert(&mut registry, RegistryKind::Rules, &mut page1, *account).unwrap();
+        }
+        assert_eq!((registry.rules, registry.roles), (RegistryPage::CAPACITY as u32 + 2, 0));
+
+        // Removing from the first page requires the last page
+        assert_eq!(
+            registry_remove(&mut registry, RegistryKind::Rules, &mut page0, None, &accounts[0]),
+            err!(Errors::MissingRegistryAccount)
+        );
+        registry_remove(&mut registry, RegistryKind::Rules, &mut page0, Some(&mut page1), &accounts[0]).unwrap();
+        assert_eq!(page0.accounts[0], accounts[RegistryPage::CAPACITY + 1]);
+        assert_eq!(page1.accounts, vec![accounts[RegistryPage::CAPACITY]]);
+        // Removing from the last page
+        assert_eq!(
+            registry_remove(&mut registry, RegistryKind::Rules, &mut page1, None, &accounts[0]),
+            err!(Errors::RegistryEntryNotFound)
+        );
+        registry_remove(&mut registry, RegistryKind::Rules, &mut page1, None, &accounts[RegistryPage::CAPACITY]).unwrap();
+        assert!(page1.accounts.is_empty());
+        assert_eq!(registry.rules, RegistryPage::CAPACITY as u32);
+        // The first page is the last one again
+        assert_eq!(
+            registry_remove(&mut registry, RegistryKind::Rules, &mut page0, Some(&mut page1), &accounts[1]),
+            err!(Errors::InvalidRegistryPage)
+        );
+        registry_remove(&mut registry, RegistryKind::Rules, &mut page0, None, &accounts[1]).unwrap();
+        assert_eq!(registry.rules, RegistryPage::CAPACITY as u32 - 1);
+    }
+
+    #[test]
+    fn test_wallet_roles() {
+        let mut wallet_roles = WalletRoles {
+            file_id: Pubkey::default(),
+            wallet: Pubkey::default(),
+            roles: vec![],
+            bump: 0,
+            payer: Pubkey::default(),
+        };
+        let roles: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+        wallet_roles.roles = roles.clone();
+        // The space grows with the number of Roles listed
+        assert_eq!(WalletRoles::space(roles.len()), wallet_roles.try_to_vec().unwrap().len() + 8);
+        wallet_roles_remove(&mut wallet_roles, &roles[0]).unwrap();
+        assert_eq!(wallet_roles.roles.len(), roles.len() - 1);
+        assert_eq!(
+            wallet_roles_remove(&mut wallet_roles, &roles[0]),
+            err!(Errors::RegistryEntryNotFound)
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 
+/// Legacy Role seed for the address (only used to migrate accounts, see `role_seed()`).
 pub fn address_or_wildcard(address: &Option<Pubkey>) -> &[u8] {
     if address.is_none() {
         return b"*".as_ref();
@@ -1,18 +1,48 @@
+use crate::state::{Namespaces, PermissionRegistry, Rule};
 use crate::Errors;
+use crate::MAX_NAME_LENGTH;
 use anchor_lang::prelude::*;
 
-pub fn valid_rule(text: &String, allow_wildcard: bool) -> bool {
-    if text.is_empty() || text.as_bytes().len() > 16 {
+/// Characters allowed on Role, Resource and Permission names (besides ASCII alphanumeric).
+pub const NAME_SPECIAL_CHARS: [char; 4] = ['-', '_', '.', ':'];
+
+fn valid_name_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || NAME_SPECIAL_CHARS.contains(&char)
+}
+
+/// Validates a Role, Resource or Permission (1-64 ASCII alphanumeric characters or "-", "_", ".", ":").
+///
+/// When `allow_wildcard` is set (Resource and Permission), the text can also be a pattern:
+///   - "*" matches everything.
+///   - A trailing "*" matches any value starting with the preceding prefix (e.g. "Report*").
+///   - "/" separates hierarchical segments (e.g. "docs/finance/*"), segments can't be empty.
+pub fn valid_rule(text: &str, allow_wildcard: bool) -> bool {
+    if text.is_empty() || text.len() > MAX_NAME_LENGTH {
         return false;
     }
-    for char in text.chars() {
-        if !char.is_ascii_alphanumeric() {
-            // Allow wildcard character "*" on all fields but Role.
-            if allow_wildcard && char == '*' && text.as_bytes().len() == 1 {
+    if !allow_wildcard {
+        return text.chars().all(valid_name_char);
+    }
+    // The wildcard "*" is only allowed as the very last character.
+    let (body, wildcard) = match text.strip_suffix('*') {
+        Some(prefix) => (prefix, true),
+        None => (text, false),
+    };
+    if body.is_empty() {
+        return wildcard;
+    }
+    let segments: Vec<&str> = body.split('/').collect();
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.is_empty() {
+            // Only allowed after a trailing "/" followed by the wildcard: "docs/*"
+            if index > 0 && index == segments.len() - 1 && wildcard {
                 continue;
             }
             return false;
         }
+        if !segment.chars().all(valid_name_char) {
+            return false;
+        }
     }
 
     true
@@ -26,12 +56,62 @@ pub fn valid_rules(role: &String, resource: &String, permission: &String) -> boo
     true
 }
 
+/// Checks if the requested value (rule1) is matched by the rule pattern (rule2):
+///   - "*" matches everything.
+///   - "Report*" matches any value starting with "Report" ("Report", "ReportSales", etc.)
+///   - "docs/finance/*" matches everything below "docs/finance/" (but not "docs/finance" itself).
+///   - Anything else must be an exact (case sensitive) match.
+///
+/// Patterns can also be requested (e.g. when adding a new rule), in which case the "*"
+/// is compared literally, so "docs/*" only matches patterns at least as restrictive as itself.
 pub fn allowed_perm(rule1: &String, rule2: &String) -> bool {
-    if rule1 == rule2 || rule2 == "*" {
-        return true;
+    match rule2.strip_suffix('*') {
+        Some(prefix) => rule1.starts_with(prefix),
+        None => rule1 == rule2,
     }
+}
 
-    false
+/// Validates a Rule granting either a single Permission or a set of them (bitmask),
+/// in which case the Permission must be empty.
+pub fn valid_rule_data(role: &String, resource: &String, permission: &String, permissions: u64) -> bool {
+    if permissions == 0 {
+        return valid_rules(role, resource, permission);
+    }
+    permission.is_empty() && valid_rule(role, false) && valid_rule(resource, true)
+}
+
+/// Checks if the rule grants the requested permission, either matching its Permission
+/// (see `allowed_perm()`) or including it on its permission set (bitmask over the PermissionRegistry).
+pub fn rule_grants(rule: &Rule, permission: &String, registry: Option<&PermissionRegistry>) -> Result<bool> {
+    if !rule.is_permission_set() {
+        return Ok(allowed_perm(permission, &rule.permission));
+    }
+    match registry {
+        Some(registry) if registry.file_id == rule.file_id => {
+            Ok(rule.permissions & registry.mask(permission) != 0)
+        }
+        _ => err!(Errors::MissingPermissionsAccount),
+    }
+}
+
+/// Gets the permissions granted by a rule: either its Permission or the registered
+/// permissions included on its permission set (bitmask).
+pub fn rule_permissions(
+    permission: &str,
+    permissions: u64,
+    registry: Option<&PermissionRegistry>,
+) -> Result<Vec<String>> {
+    if permissions == 0 {
+        return Ok(vec![permission.to_string()]);
+    }
+    let registry = match registry {
+        Some(registry) => registry,
+        None => return err!(Errors::MissingPermissionsAccount),
+    };
+    if permissions & !registry.full_mask() != 0 {
+        return err!(Errors::InvalidPermissionSet);
+    }
+    Ok(registry.names(permissions).into_iter().cloned().collect())
 }
 
 pub fn validate_ns_permission(namespace: &String) -> Result<()> {
@@ -43,9 +123,24 @@ pub fn validate_ns_permission(namespace: &String) -> Result<()> {
     Ok(())
 }
 
+/// Validates the Resource of the rules managing Roles and Rules:
+///   - "AssignRole", "DeleteAssignRole" & "UpdateAssignRole": an AddressType ("Wallet", "Nft", "Collection") or wildcard "*".
+///   - "AddRuleNSRole", "DeleteRuleNSRole" & "UpdateRuleNSRole": a namespace (0-255) or wildcard "*".
+pub fn validate_rule_resource(namespace: u8, resource: &String) -> Result<()> {
+    let assign_role = namespace == Namespaces::AssignRole as u8 || namespace == Namespaces::DeleteAssignRole as u8 || namespace == Namespaces::UpdateAssignRole as u8;
+    if assign_role && !matches!(resource.as_str(), "Wallet" | "Nft" | "Collection" | "*") {
+        return err!(Errors::InvalidAddressType);
+    }
+    if namespace == Namespaces::AddRuleNSRole as u8 || namespace == Namespaces::DeleteRuleNSRole as u8 || namespace == Namespaces::UpdateRuleNSRole as u8 {
+        validate_ns_permission(resource)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use solana_program::pubkey;
 
     #[test]
     fn test_valid_rules() {
@@ -58,18 +153,38 @@ mod tests {
             valid_rules(&"".to_string(), &"b".to_string(), &"c".to_string()),
             false
         );
-        // 16 Characters max per Role, Resource or Permission.
+        // 64 Characters max per Role, Resource or Permission.
         assert_eq!(
             valid_rules(
-                &"12345678901234567".to_string(),
+                &"InvoiceApproverForTheBillingDepartment".to_string(),
                 &"b".to_string(),
                 &"c".to_string()
             ),
+            true
+        );
+        assert_eq!(
+            valid_rules(&"a".repeat(64), &"b".to_string(), &"c".to_string()),
+            true
+        );
+        assert_eq!(
+            valid_rules(&"a".repeat(65), &"b".to_string(), &"c".to_string()),
+            false
+        );
+        // Only Alphanumeric chars and "-", "_", ".", ":" allowed.
+        assert_eq!(
+            valid_rules(
+                &"billing:invoice".to_string(),
+                &"my-resource_v1.0".to_string(),
+                &"C".to_string()
+            ),
+            true
+        );
+        assert_eq!(
+            valid_rules(&"a b".to_string(), &"b".to_string(), &"C".to_string()),
             false
         );
-        // Only Alphanumeric chars allowed.
         assert_eq!(
-            valid_rules(&"-".to_string(), &"b".to_string(), &"C".to_string()),
+            valid_rules(&"a".to_string(), &"b#".to_string(), &"C".to_string()),
             false
         );
         // Allow "*" on all fields but Role.
@@ -81,6 +196,26 @@ mod tests {
             valid_rules(&"*".to_string(), &"B".to_string(), &"C".to_string()),
             false
         );
+        // Prefix and hierarchical patterns allowed on Resource and Permission.
+        assert_eq!(
+            valid_rules(
+                &"A".to_string(),
+                &"docs/finance/*".to_string(),
+                &"Report*".to_string()
+            ),
+            true
+        );
+        assert_eq!(valid_rule(&"docs/finance".to_string(), true), true);
+        assert_eq!(valid_rule(&"docs/finance".to_string(), false), false);
+        assert_eq!(valid_rule(&"Report*".to_string(), false), false);
+        // Wildcard only allowed as the last character.
+        assert_eq!(valid_rule(&"Re*port".to_string(), true), false);
+        assert_eq!(valid_rule(&"**".to_string(), true), false);
+        // Empty segments are not allowed.
+        assert_eq!(valid_rule(&"/docs".to_string(), true), false);
+        assert_eq!(valid_rule(&"docs/".to_string(), true), false);
+        assert_eq!(valid_rule(&"docs//a".to_string(), true), false);
+        assert_eq!(valid_rule(&"/*".to_string(), true), false);
     }
 
     #[test]
@@ -88,6 +223,117 @@ mod tests {
         assert_eq!(allowed_perm(&"add".to_string(), &"add".to_string()), true);
         assert_eq!(allowed_perm(&"add".to_string(), &"edit".to_string()), false);
         assert_eq!(allowed_perm(&"add".to_string(), &"*".to_string()), true);
+        // Prefix wildcards
+        assert_eq!(allowed_perm(&"Report".to_string(), &"Report*".to_string()), true);
+        assert_eq!(allowed_perm(&"ReportSales".to_string(), &"Report*".to_string()), true);
+        assert_eq!(allowed_perm(&"Repo".to_string(), &"Report*".to_string()), false);
+        assert_eq!(allowed_perm(&"report".to_string(), &"Report*".to_string()), false);
+        // Hierarchical resources
+        assert_eq!(
+            allowed_perm(&"docs/finance/q1".to_string(), &"docs/finance/*".to_string()),
+            true
+        );
+        assert_eq!(
+            allowed_perm(&"docs/finance/q1/summary".to_string(), &"docs/finance/*".to_string()),
+            true
+        );
+        assert_eq!(
+            allowed_perm(&"docs/finance".to_string(), &"docs/finance/*".to_string()),
+            false
+        );
+        assert_eq!(
+            allowed_perm(&"docs/hr/q1".to_string(), &"docs/finance/*".to_string()),
+            false
+        );
+        // Requested patterns must be covered by the rule pattern.
+        assert_eq!(
+            allowed_perm(&"docs/finance/*".to_string(), &"docs/*".to_string()),
+            true
+        );
+        assert_eq!(allowed_perm(&"docs/*".to_string(), &"docs/finance/*".to_string()), false);
+        assert_eq!(allowed_perm(&"*".to_string(), &"Report*".to_string()), false);
+    }
+
+    fn test_rule(permission: &str, permissions: u64) -> Rule {
+        Rule {
+            file_id: pubkey!("6kJuLfs8BrKwxy28FCmcPfp4d5stv4Sr6YgV15A6s7FK"),
+            namespace: 0,
+            role: "Admin".to_string(),
+            resource: "Documents".to_string(),
+            permission: permission.to_string(),
+            permissions,
+            expires_at: None,
+            bump: 0,
+            registered: true,
+            payer: Pubkey::default(),
+        }
+    }
+
+    fn test_registry() -> PermissionRegistry {
+        PermissionRegistry {
+            file_id: pubkey!("6kJuLfs8BrKwxy28FCmcPfp4d5stv4Sr6YgV15A6s7FK"),
+            permissions: vec!["Read".to_string(), "Write".to_string(), "Delete".to_string()],
+            bump: 0,
+            payer: Pubkey::default(),
+        }
+    }
+
+    #[test]
+    fn test_valid_rule_data() {
+        let (role, resource) = (&"Admin".to_string(), &"Documents".to_string());
+        assert_eq!(valid_rule_data(role, resource, &"Read".to_string(), 0), true);
+        assert_eq!(valid_rule_data(role, resource, &"".to_string(), 0), false);
+        // Permission must be empty on permission sets.
+        assert_eq!(valid_rule_data(role, resource, &"".to_string(), 0b11), true);
+        assert_eq!(valid_rule_data(role, resource, &"Read".to_string(), 0b11), false);
+    }
+
+    #[test]
+    fn test_rule_grants() {
+        let registry = test_registry();
+        // Single permission rules don't need the registry.
+        let rule = test_rule("Read", 0);
+        assert_eq!(rule_grants(&rule, &"Read".to_string(), None), Ok(true));
+        assert_eq!(rule_grants(&rule, &"Write".to_string(), None), Ok(false));
+        // Permission sets: "Read" and "Delete"
+        let rule = test_rule("", 0b101);
+        assert_eq!(rule_grants(&rule, &"Read".to_string(), Some(&registry)), Ok(true));
+        assert_eq!(rule_grants(&rule, &"Write".to_string(), Some(&registry)), Ok(false));
+        assert_eq!(rule_grants(&rule, &"Delete".to_string(), Some(&registry)), Ok(true));
+        assert_eq!(rule_grants(&rule, &"Unknown".to_string(), Some(&registry)), Ok(false));
+        assert_eq!(
+            rule_grants(&rule, &"Read".to_string(), None),
+            err!(Errors::MissingPermissionsAccount)
+        );
+        // Registry from a different File
+        let mut other_registry = test_registry();
+        other_registry.file_id = pubkey!("Ft9dAWwsFV8wFKmdgCJAe21ZnnqtXBBAdVB3cjUyRMY9");
+        assert_eq!(
+            rule_grants(&rule, &"Read".to_string(), Some(&other_registry)),
+            err!(Errors::MissingPermissionsAccount)
+        );
+    }
+
+    #[test]
+    fn test_rule_permissions() {
+        let registry = test_registry();
+        assert_eq!(
+            rule_permissions(&"Read".to_string(), 0, None),
+            Ok(vec!["Read".to_string()])
+        );
+        assert_eq!(
+            rule_permissions(&"".to_string(), 0b110, Some(&registry)),
+            Ok(vec!["Write".to_string(), "Delete".to_string()])
+        );
+        // Unregistered bits
+        assert_eq!(
+            rule_permissions(&"".to_string(), 0b1000, Some(&registry)),
+            err!(Errors::InvalidPermissionSet)
+        );
+        assert_eq!(
+            rule_permissions(&"".to_string(), 0b1, None),
+            err!(Errors::MissingPermissionsAccount)
+        );
     }
 
     #[test]
@@ -109,4 +355,25 @@ mod tests {
             err!(Errors::InvalidNamespace)
         );
     }
+
+    #[test]
+    fn test_validate_rule_resource() {
+        for namespace in [Namespaces::AddRuleNSRole as u8, Namespaces::DeleteRuleNSRole as u8, Namespaces::UpdateRuleNSRole as u8] {
+            assert_eq!(validate_rule_resource(namespace, &"*".to_string()), Ok(()));
+            assert_eq!(validate_rule_resource(namespace, &"0".to_string()), Ok(()));
+            assert_eq!(
+                validate_rule_resource(namespace, &"Wallet".to_string()),
+                err!(Errors::InvalidNamespace)
+            );
+        }
+        for namespace in [Namespaces::AssignRole as u8, Namespaces::DeleteAssignRole as u8, Namespaces::UpdateAssignRole as u8] {
+            assert_eq!(validate_rule_resource(namespace, &"Wallet".to_string()), Ok(()));
+            assert_eq!(validate_rule_resource(namespace, &"*".to_string()), Ok(()));
+            assert_eq!(
+                validate_rule_resource(namespace, &"0".to_string()),
+                err!(Errors::InvalidAddressType)
+            );
+        }
+        assert_eq!(validate_rule_resource(Namespaces::Rule as u8, &"Report*".to_string()), Ok(()));
+    }
 }
@@ -0,0 +1,143 @@
+use crate::state::role::AddressType;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+/// Version of the seed encoding used to derive Rule and Role PDAs.
+pub const SEED_VERSION: u8 = 1;
+
+///  SeedTags (identifies the type of each value encoded into a seed):
+///     0 => Namespace
+///     1 => Role
+///     2 => Resource
+///     3 => Permission
+///     4 => AddressType
+///     5 => Address
+///     6 => Wildcard (Role applied to any address)
+#[repr(u8)]
+pub enum SeedTags {
+    Namespace = 0,
+    Role = 1,
+    Resource = 2,
+    Permission = 3,
+    AddressType = 4,
+    Address = 5,
+    Wildcard = 6,
+}
+
+/// Encodes the seed values as: version | (tag | length (u32 LE) | value)*
+/// so different tuples can never produce the same bytes (e.g. ("ab","c") and ("a","bc")).
+pub struct SeedEncoder {
+    data: Vec<u8>,
+}
+
+impl SeedEncoder {
+    pub fn new() -> Self {
+        SeedEncoder {
+            data: vec![SEED_VERSION],
+        }
+    }
+
+    pub fn push(mut self, tag: SeedTags, value: &[u8]) -> Self {
+        self.data.push(tag as u8);
+        self.data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    /// Hashes (SHA-256) the encoded values into a single 32 bytes seed.
+    pub fn hash(&self) -> [u8; 32] {
+        hash(&self.data).to_bytes()
+    }
+}
+
+impl Default for SeedEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gets the seed used to derive Rule PDAs: [b"rule", rule_seed(..), file_id]
+///
+/// Rules granting a permission set (bitmask) are stored with an empty Permission, so there can only
+/// be one permission-set Rule per (namespace, role, resource): changing its set requires deleting and adding it again.
+pub fn rule_seed(namespace: u8, role: &str, resource: &str, permission: &str) -> [u8; 32] {
+    SeedEncoder::new()
+        .push(SeedTags::Namespace, &[namespace])
+        .push(SeedTags::Role, role.as_bytes())
+        .push(SeedTags::Resource, resource.as_bytes())
+        .push(SeedTags::Permission, permission.as_bytes())
+        .hash()
+}
+
+/// Gets the seed used to derive Role PDAs: [b"role", role_seed(..), file_id]
+/// Empty addresses are considered wildcards "*" (role applied to all addresses).
+pub fn role_seed(role: &str, address_type: &AddressType, address: &Option<Pubkey>) -> [u8; 32] {
+    let encoder = SeedEncoder::new()
+        .push(SeedTags::Role, role.as_bytes())
+        .push(SeedTags::AddressType, &[address_type.clone() as u8]);
+    match address {
+        Some(address) => encoder.push(SeedTags::Address, address.as_ref()),
+        None => encoder.push(SeedTags::Wildcard, &[]),
+    }
+    .hash()
+}
+
+/// Names up to this length are used as raw seeds, so accounts created before
+/// names were allowed to grow beyond 16 bytes remain resolvable.
+pub const RAW_SEED_MAX_LENGTH: usize = 16;
+
+/// Gets the legacy PDA seed for a Role, Resource or Permission name (only used to migrate accounts).
+/// Short names are used as they are, while longer ones are hashed (SHA-256)
+/// to fit within the 32 bytes limit of a PDA seed.
+pub fn name_seed(name: &str) -> Vec<u8> {
+    if name.len() <= RAW_SEED_MAX_LENGTH {
+        return name.as_bytes().to_vec();
+    }
+    hash(name.as_bytes()).to_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey;
+
+    #[test]
+    fn test_name_seed() {
+        // Short names are kept as they are.
+        assert_eq!(name_seed("Admin"), b"Admin".to_vec());
+        assert_eq!(name_seed("1234567890123456"), b"1234567890123456".to_vec());
+        // Longer names are hashed into 32 bytes.
+        let seed = name_seed("billing:invoice-approver");
+        assert_eq!(seed.len(), 32);
+        assert_eq!(seed, hash(b"billing:invoice-approver").to_bytes().to_vec());
+        assert_ne!(seed, name_seed("billing:invoice-approver2"));
+    }
+
+    #[test]
+    fn test_rule_seed() {
+        // Concatenated values are no longer ambiguous.
+        assert_ne!(rule_seed(0, "ab", "c", "d"), rule_seed(0, "a", "bc", "d"));
+        assert_ne!(rule_seed(0, "a", "b", "cd"), rule_seed(0, "a", "bc", "d"));
+        assert_ne!(rule_seed(0, "a", "b", "c"), rule_seed(1, "a", "b", "c"));
+        assert_eq!(rule_seed(0, "a", "b", "c"), rule_seed(0, "a", "b", "c"));
+    }
+
+    #[test]
+    fn test_role_seed() {
+        let address = pubkey!("6kJuLfs8BrKwxy28FCmcPfp4d5stv4Sr6YgV15A6s7FK");
+        // Same address assigned as Wallet and Collection does not collide.
+        assert_ne!(
+            role_seed("Admin", &AddressType::Wallet, &Some(address)),
+            role_seed("Admin", &AddressType::Collection, &Some(address))
+        );
+        // Wildcard is different from any address.
+        assert_ne!(
+            role_seed("Admin", &AddressType::Wallet, &None),
+            role_seed("Admin", &AddressType::Wallet, &Some(address))
+        );
+        assert_eq!(
+            role_seed("Admin", &AddressType::Wallet, &Some(address)),
+            role_seed("Admin", &AddressType::Wallet, &Some(address))
+        );
+    }
+}
@@ -17,3 +17,48 @@ pub fn transfer_lamports(
     **to_account.try_borrow_mut_lamports()? += lamports;
     Ok(())
 }
+
+/// Grows a program account to `size` (when smaller), the payer covering the rent of the new size.
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    size: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if account.data_len() >= size {
+        return Ok(());
+    }
+    let missing = Rent::get()?.minimum_balance(size).saturating_sub(account.lamports());
+    if missing > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, missing)?;
+    }
+    account.realloc(size, false)?;
+    Ok(())
+}
+
+/// Resizes a program account, the payer covering the extra rent when it grows and the receiver
+/// getting back the rent no longer required when it shrinks.
+pub fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    size: usize,
+    payer: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if size > account.data_len() {
+        return grow_account(account, size, payer, system_program);
+    }
+    account.realloc(size, false)?;
+    let excess = account.lamports().saturating_sub(Rent::get()?.minimum_balance(size));
+    if excess > 0 {
+        transfer_lamports(account, receiver, excess)?;
+    }
+    Ok(())
+}
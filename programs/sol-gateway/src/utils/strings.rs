@@ -11,6 +11,15 @@ pub fn validate_string_len(text: &String, min: usize, max: usize) -> Result<Stri
     Ok(text.clone())
 }
 
+/// Cuts the text to at most "max" bytes (on a char boundary).
+pub fn truncate_string(text: &str, max: usize) -> String {
+    let mut end = text.len().min(max);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,4 +38,12 @@ mod tests {
             err!(Errors::StringTooLong)
         );
     }
+
+    #[test]
+    fn test_truncate_string() {
+        assert_eq!(truncate_string("ABC", 5), "ABC");
+        assert_eq!(truncate_string("ABC", 2), "AB");
+        // Multi-byte chars are never split
+        assert_eq!(truncate_string("AÑ", 2), "A");
+    }
 }
@@ -1,6 +1,23 @@
 import * as anchor from "@project-serum/anchor";
 import { expect, assert } from "chai";
-import { file_pda, safe_airdrop, metadata_pda } from "./common";
+import {
+  EVENT_ACCOUNTS,
+  cpi_events,
+  file_pda,
+  string_value,
+  safe_airdrop,
+  metadata_pda,
+  schema_pda,
+  rule_pda,
+  registry_accounts,
+  registry_pda,
+  registry_page_pda,
+  config_pda,
+  role_pda,
+  registry_pages,
+  wallet_roles_pda,
+  policy_hash,
+} from "./common";
 import {
   FILE_ID,
   PROVIDER,
@@ -9,6 +26,10 @@ import {
   ALLOWED_WALLET,
   ANOTHER_WALLET,
   accountTypes,
+  namespaces,
+  registryKinds,
+  LEGACY_FILE_ID,
+  LEGACY_AUTHORITY,
 } from "./constants";
 
 describe("1.- Initialize FILE and Metadata", () => {
@@ -54,10 +75,13 @@ describe("1.- Initialize FILE and Metadata", () => {
         size: new anchor.BN(1073741824), // 1 GB
         checksum: "351101afcc166d0be1299d55bdfa61a4",
         metadata: null,
+        schema: null,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000), // Add this line
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDA,
+        metadataSchema: null,
         fileMetadata: null, // Note: here we NEED to pass null or else the program will try to create a metadata account
       })
       .rpc();
@@ -98,13 +122,16 @@ describe("1.- Initialize FILE and Metadata", () => {
         size: new anchor.BN(1073741824), // 1 GB
         checksum: "351101afcc166d0be1299d55bdfa61a4",
         metadata: [
-          { key: "author", value: "John Doe" },
-          { key: "version", value: "1.0" },
+          { key: "author", value: string_value("John Doe") },
+          { key: "version", value: string_value("1.0") },
         ],
+        schema: null,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000), // Add this line
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: newFilePDA,
+        metadataSchema: null,
         fileMetadata: newMetadataPDA,
       })
       .rpc();
@@ -120,8 +147,8 @@ describe("1.- Initialize FILE and Metadata", () => {
 
     expect(metadata.fileId.toBase58()).to.equal(newFileId.toBase58());
     expect(metadata.metadata).to.deep.equal([
-      { key: "author", value: "John Doe" },
-      { key: "version", value: "1.0" },
+      { key: "author", value: string_value("John Doe") },
+      { key: "version", value: string_value("1.0") },
     ]);
   });
 
@@ -140,10 +167,13 @@ describe("1.- Initialize FILE and Metadata", () => {
         size: new anchor.BN(1073741824), // 1 GB
         checksum: "351101afcc166d0be1299d55bdfa61a4",
         metadata: null,
+        schema: null,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: newFilePDA,
+        metadataSchema: null,
         fileMetadata: null,
         rentPayer: ANOTHER_WALLET.publicKey,
       })
@@ -187,10 +217,18 @@ describe("1.- Initialize FILE and Metadata", () => {
           checksum: "351101afcc166d0be1299d55bdfa61a4",
           accountType: accountTypes.Basic,
           expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
+          maxRoles: null,
+          maxRules: null,
         })
         .accounts({
+          ...EVENT_ACCOUNTS,
           file: filePDA,
           signer: unauthorized_keypair.publicKey,
+          solGatewayRole: null,
+          solGatewayRule: null,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: null,
         })
         .signers([unauthorized_keypair])
         .rpc();
@@ -213,9 +251,17 @@ describe("1.- Initialize FILE and Metadata", () => {
         checksum: "351101afcc166d0be1299d55bdfa61a4",
         accountType: accountTypes.Basic,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
+        maxRoles: null,
+        maxRules: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
       })
       .rpc();
     let file = await PROGRAM.account.file.fetch(filePDA);
@@ -237,10 +283,18 @@ describe("1.- Initialize FILE and Metadata", () => {
         checksum: "351101afcc166d0be1299d55bdfa61a4",
         accountType: accountTypes.Basic,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
+        maxRoles: null,
+        maxRules: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDA,
         signer: RECOVERY_KEYPAIR.publicKey,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
       })
       .signers([RECOVERY_KEYPAIR])
       .rpc();
@@ -267,13 +321,16 @@ describe("1.- Initialize FILE and Metadata", () => {
         size: new anchor.BN(1073741824), // 1 GB
         checksum: "351101afcc166d0be1299d55bdfa61a4",
         metadata: [
-          { key: "author", value: "John Doe" },
-          { key: "version", value: "1.0" },
+          { key: "author", value: string_value("John Doe") },
+          { key: "version", value: string_value("1.0") },
         ],
+        schema: null,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDA,
+        metadataSchema: null,
         fileMetadata: metadataPDA,
       })
       .rpc();
@@ -282,24 +339,335 @@ describe("1.- Initialize FILE and Metadata", () => {
     await PROGRAM.methods
       .updateFileMetadata({
         metadata: [
-          { key: "author", value: "Jane Doe" },
-          { key: "version", value: "2.0" },
-          { key: "status", value: "updated" },
+          { key: "author", value: string_value("Jane Doe") },
+          { key: "version", value: string_value("2.0") },
+          { key: "status", value: string_value("updated") },
         ],
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDA,
         fileMetadata: metadataPDA,
+        metadataSchema: await schema_pda(fileId),
         signer: PROVIDER.wallet.publicKey,
+        rentPayer: PROVIDER.wallet.publicKey,
+        rentReceiver: PROVIDER.wallet.publicKey,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
       })
       .rpc();
 
     let metadata = await PROGRAM.account.fileMetadata.fetch(metadataPDA);
     expect(metadata.metadata).to.deep.equal([
-      { key: "author", value: "Jane Doe" },
-      { key: "version", value: "2.0" },
-      { key: "status", value: "updated" },
+      { key: "author", value: string_value("Jane Doe") },
+      { key: "version", value: string_value("2.0") },
+      { key: "status", value: string_value("updated") },
+    ]);
+
+    // Metadata account is resized to fit larger metadata
+    const provenance = "x".repeat(256);
+    await PROGRAM.methods
+      .updateFileMetadata({
+        metadata: [
+          { key: "author", value: string_value("Jane Doe") },
+          { key: "provenance", value: string_value(provenance) },
+        ],
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        fileMetadata: metadataPDA,
+        metadataSchema: await schema_pda(fileId),
+        signer: PROVIDER.wallet.publicKey,
+        rentPayer: PROVIDER.wallet.publicKey,
+        rentReceiver: PROVIDER.wallet.publicKey,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+    metadata = await PROGRAM.account.fileMetadata.fetch(metadataPDA);
+    expect(metadata.metadata).to.deep.equal([
+      { key: "author", value: string_value("Jane Doe") },
+      { key: "provenance", value: string_value(provenance) },
+    ]);
+
+    // Unchanged (reordered) metadata still requires access
+    try {
+      await PROGRAM.methods
+        .updateFileMetadata({
+          metadata: [
+            { key: "provenance", value: string_value(provenance) },
+            { key: "author", value: string_value("Jane Doe") },
+          ],
+        })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          file: filePDA,
+          fileMetadata: metadataPDA,
+          metadataSchema: await schema_pda(fileId),
+          signer: ANOTHER_WALLET.publicKey,
+          rentPayer: PROVIDER.wallet.publicKey,
+          rentReceiver: PROVIDER.wallet.publicKey,
+          solGatewayRole: null,
+          solGatewayRule: null,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: null,
+        })
+        .signers([ANOTHER_WALLET])
+        .rpc();
+      throw new Error("Unauthorized wallets shouldn't update metadata!");
+    } catch (error) {
+      // Denied before evaluating roles, as the File charges fees and no seed account was provided
+      expect(error.toString()).to.include("MissingSeedAccount");
+    }
+
+    // Duplicated keys are rejected
+    try {
+      await PROGRAM.methods
+        .updateFileMetadata({
+          metadata: [
+            { key: "author", value: string_value("Jane Doe") },
+            { key: "author", value: string_value("John Doe") },
+          ],
+        })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          file: filePDA,
+          fileMetadata: metadataPDA,
+          metadataSchema: await schema_pda(fileId),
+          signer: PROVIDER.wallet.publicKey,
+          rentPayer: PROVIDER.wallet.publicKey,
+          rentReceiver: PROVIDER.wallet.publicKey,
+          solGatewayRole: null,
+          solGatewayRule: null,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: null,
+        })
+        .rpc();
+      throw new Error("Duplicated metadata keys shouldn't be allowed!");
+    } catch (error) {
+      expect(error.toString()).to.include("DuplicatedMetadataKey");
+    }
+  });
+
+  it("Set and remove metadata entries", async () => {
+    const fileId = anchor.web3.Keypair.generate().publicKey;
+    const filePDA = await file_pda(fileId);
+    const metadataPDA = await metadata_pda(fileId);
+    await PROGRAM.methods
+      .initializeFiles({
+        id: fileId,
+        recovery: null,
+        name: "file1",
+        cached: false,
+        size: new anchor.BN(1024),
+        checksum: "351101afcc166d0be1299d55bdfa61a4",
+        metadata: [
+          { key: "author", value: string_value("John Doe") },
+          { key: "version", value: string_value("1.0") },
+        ],
+        schema: null,
+        expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        metadataSchema: null,
+        fileMetadata: metadataPDA,
+      })
+      .rpc();
+    const accounts = {
+      ...EVENT_ACCOUNTS,
+      file: filePDA,
+      fileMetadata: metadataPDA,
+      metadataSchema: await schema_pda(fileId),
+      signer: PROVIDER.wallet.publicKey,
+      rentPayer: PROVIDER.wallet.publicKey,
+      rentReceiver: PROVIDER.wallet.publicKey,
+      solGatewayRole: null,
+      solGatewayRule: null,
+      solGatewayToken: null,
+      solGatewayMetadata: null,
+      solGatewaySeed: null,
+    };
+
+    // Set (add or replace) a single key
+    let signature = await PROGRAM.methods
+      .setMetadataEntry({ key: "license", value: string_value("MIT") })
+      .accounts(accounts)
+      .rpc({ commitment: "confirmed" });
+    let [event]: any = await cpi_events(signature, "MetadataUpdated");
+    expect(event.keys).to.deep.equal(["license"]);
+    await PROGRAM.methods
+      .setMetadataEntry({ key: "version", value: string_value("2.0") })
+      .accounts(accounts)
+      .rpc();
+    // Typed values
+    await PROGRAM.methods
+      .setMetadataEntry({
+        key: "max_downloads",
+        value: { u64: { 0: new anchor.BN(100) } },
+      })
+      .accounts(accounts)
+      .rpc();
+
+    // The rent refunded when metadata shrinks goes to the account that paid for it
+    try {
+      await PROGRAM.methods
+        .removeMetadataEntry("author")
+        .accounts({ ...accounts, rentReceiver: ANOTHER_WALLET.publicKey })
+        .rpc();
+      throw new Error("Metadata rent can't be refunded to other accounts!");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidRentReceiver");
+    }
+
+    // Remove a single key
+    signature = await PROGRAM.methods
+      .removeMetadataEntry("author")
+      .accounts(accounts)
+      .rpc({ commitment: "confirmed" });
+    [event] = await cpi_events(signature, "MetadataUpdated");
+    expect(event.keys).to.deep.equal(["author"]);
+
+    const metadata: any = await PROGRAM.account.fileMetadata.fetch(
+      metadataPDA
+    );
+    expect(metadata.metadata.length).to.equal(3);
+    expect(metadata.metadata.slice(0, 2)).to.deep.equal([
+      { key: "version", value: string_value("2.0") },
+      { key: "license", value: string_value("MIT") },
     ]);
+    expect(metadata.metadata[2].key).to.equal("max_downloads");
+    expect(metadata.metadata[2].value.u64[0].toNumber()).to.equal(100);
+
+    try {
+      await PROGRAM.methods
+        .removeMetadataEntry("author")
+        .accounts(accounts)
+        .rpc();
+      throw new Error("Missing metadata keys can't be removed!");
+    } catch (error) {
+      expect(error.toString()).to.include("MetadataKeyNotFound");
+    }
+
+    // Typed metadata is already counted on the File, it can't be migrated again
+    const file = await PROGRAM.account.file.fetch(filePDA);
+    try {
+      await PROGRAM.methods
+        .migrateMetadata()
+        .accounts({
+          authority: PROVIDER.wallet.publicKey,
+          file: filePDA,
+          legacyFileMetadata: metadataPDA,
+        })
+        .rpc();
+      throw new Error("Typed metadata shouldn't be migrated!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("MetadataAlreadyMigrated");
+    }
+    expect(
+      (await PROGRAM.account.file.fetch(filePDA)).childrenCount
+    ).to.equal(file.childrenCount);
+  });
+
+  it("Enforce metadata schema", async () => {
+    const fileId = anchor.web3.Keypair.generate().publicKey;
+    const filePDA = await file_pda(fileId);
+    const metadataPDA = await metadata_pda(fileId);
+    const schemaPDA = await schema_pda(fileId);
+    await PROGRAM.methods
+      .initializeFiles({
+        id: fileId,
+        recovery: null,
+        name: "file1",
+        cached: false,
+        size: new anchor.BN(1024),
+        checksum: "351101afcc166d0be1299d55bdfa61a4",
+        metadata: [{ key: "license", value: string_value("MIT") }],
+        schema: [
+          {
+            key: "license",
+            valueType: { string: {} },
+            required: true,
+            maxLength: 16,
+          },
+        ],
+        expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        metadataSchema: schemaPDA,
+        fileMetadata: metadataPDA,
+      })
+      .rpc();
+    const accounts = {
+      ...EVENT_ACCOUNTS,
+      file: filePDA,
+      fileMetadata: metadataPDA,
+      metadataSchema: schemaPDA,
+      signer: PROVIDER.wallet.publicKey,
+      rentPayer: PROVIDER.wallet.publicKey,
+      rentReceiver: PROVIDER.wallet.publicKey,
+      solGatewayRole: null,
+      solGatewayRule: null,
+      solGatewayToken: null,
+      solGatewayMetadata: null,
+      solGatewaySeed: null,
+    };
+
+    // Required keys can't be removed
+    try {
+      await PROGRAM.methods
+        .removeMetadataEntry("license")
+        .accounts(accounts)
+        .rpc();
+      throw new Error("Required metadata keys can't be removed!");
+    } catch (error) {
+      expect(error.toString()).to.include("MissingMetadataKey");
+    }
+
+    // Values must match the schema type
+    try {
+      await PROGRAM.methods
+        .setMetadataEntry({
+          key: "license",
+          value: { u64: { 0: new anchor.BN(1) } },
+        })
+        .accounts(accounts)
+        .rpc();
+      throw new Error("Metadata values must match the schema type!");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidMetadataType");
+    }
+
+    // Schema can be replaced by the File authority
+    await PROGRAM.methods
+      .setMetadataSchema({ fields: [] })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        metadataSchema: schemaPDA,
+      })
+      .rpc();
+    await PROGRAM.methods
+      .removeMetadataEntry("license")
+      .accounts(accounts)
+      .rpc();
+    const schema = await PROGRAM.account.metadataSchema.fetch(schemaPDA);
+    expect(schema.fields).to.deep.equal([]);
   });
 
   it("Delete file", async () => {
@@ -315,10 +683,13 @@ describe("1.- Initialize FILE and Metadata", () => {
         size: new anchor.BN(1048576), // 1 MB
         checksum: "123456789abcdef0123456789abcdef0",
         metadata: null,
+        schema: null,
         expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDAToDelete,
+        metadataSchema: null,
         fileMetadata: null,
       })
       .rpc();
@@ -327,11 +698,17 @@ describe("1.- Initialize FILE and Metadata", () => {
     expect(file.name).to.equal("file_del");
 
     await PROGRAM.methods
-      .deleteFile()
+      .deleteFile(false)
       .accounts({
+        ...EVENT_ACCOUNTS,
         file: filePDAToDelete,
-        authority: PROVIDER.wallet.publicKey,
+        signer: PROVIDER.wallet.publicKey,
         collector: PROVIDER.wallet.publicKey,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
       })
       .rpc();
 
@@ -342,4 +719,328 @@ describe("1.- Initialize FILE and Metadata", () => {
       expect(erro.message).to.include("Account does not exist");
     }
   });
+
+  it("Files created with counters are not migrating", async () => {
+    const file = await PROGRAM.account.file.fetch(filePDA);
+    expect(file.migrating).to.be.false;
+    try {
+      await PROGRAM.methods
+        .completeMigration()
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          authority: PROVIDER.wallet.publicKey,
+          solGatewayFile: filePDA,
+        })
+        .rpc();
+      throw new Error("Only migrating Files can complete their migration!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("FileNotMigrating");
+    }
+  });
+
+  it("Legacy Roles and Rules are moved to their new addresses", async () => {
+    await safe_airdrop(PROVIDER.connection, LEGACY_AUTHORITY.publicKey);
+    const legacyFilePDA = await file_pda(LEGACY_FILE_ID);
+    const authority = LEGACY_AUTHORITY.publicKey;
+    await PROGRAM.methods
+      .migrateFile()
+      .accounts({ authority, legacyFile: legacyFilePDA })
+      .signers([LEGACY_AUTHORITY])
+      .rpc();
+    let file = await PROGRAM.account.file.fetch(legacyFilePDA);
+    expect(file.migrating).to.be.true;
+    expect(file.rolesCount).to.equal(0);
+    expect(file.rulesCount).to.equal(0);
+
+    // Legacy seeds: [role, address, file_id] & [namespace, role, resource, permission, file_id]
+    const [legacyRolePDA] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("Legacy"), authority.toBuffer(), LEGACY_FILE_ID.toBuffer()],
+      PROGRAM.programId
+    );
+    const [legacyRulePDA] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from([namespaces.Rule]),
+        Buffer.from("Legacy"),
+        Buffer.from("Archive"),
+        Buffer.from("Read"),
+        LEGACY_FILE_ID.toBuffer(),
+      ],
+      PROGRAM.programId
+    );
+    const rolePDA = await role_pda("Legacy", authority, 0, LEGACY_FILE_ID);
+    const rulePDA = await rule_pda(
+      "Legacy",
+      "Archive",
+      "Read",
+      namespaces.Rule,
+      LEGACY_FILE_ID
+    );
+    const migrateRole = async (collector: anchor.web3.PublicKey) =>
+      PROGRAM.methods
+        .migrateRole()
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Roles, LEGACY_FILE_ID)),
+          authority,
+          solGatewayFile: legacyFilePDA,
+          legacyRole: legacyRolePDA,
+          role: rolePDA,
+          walletRoles: await wallet_roles_pda(authority, LEGACY_FILE_ID),
+          collector,
+        })
+        .signers([LEGACY_AUTHORITY])
+        .rpc({ commitment: "confirmed" });
+
+    // The rent of legacy accounts goes back to the File authority
+    try {
+      await migrateRole(PROVIDER.wallet.publicKey);
+      throw new Error("Legacy rent shouldn't be collected by other wallets!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("Unauthorized");
+    }
+
+    await migrateRole(authority);
+    expect(await PROVIDER.connection.getAccountInfo(legacyRolePDA)).to.be.null;
+    const role = await PROGRAM.account.role.fetch(rolePDA);
+    expect(role.role).to.equal("Legacy");
+    expect(role.address.toBase58()).to.equal(authority.toBase58());
+    expect(role.registered).to.be.true;
+    const walletRoles = await PROGRAM.account.walletRoles.fetch(
+      await wallet_roles_pda(authority, LEGACY_FILE_ID)
+    );
+    expect(walletRoles.roles.map((r) => r.toBase58())).to.deep.equal([
+      rolePDA.toBase58(),
+    ]);
+
+    await PROGRAM.methods
+      .migrateRule()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules, LEGACY_FILE_ID)),
+        authority,
+        solGatewayFile: legacyFilePDA,
+        legacyRule: legacyRulePDA,
+        rule: rulePDA,
+        collector: authority,
+      })
+      .signers([LEGACY_AUTHORITY])
+      .rpc({ commitment: "confirmed" });
+    expect(await PROVIDER.connection.getAccountInfo(legacyRulePDA)).to.be.null;
+    const rule = await PROGRAM.account.rule.fetch(rulePDA);
+    expect(rule.resource).to.equal("Archive");
+    expect(rule.permission).to.equal("Read");
+    expect(rule.registered).to.be.true;
+
+    // The new accounts are listed, counted and included on the policy hash
+    const roles = (await registry_pages(registryKinds.Roles, LEGACY_FILE_ID)).flat();
+    const rules = (await registry_pages(registryKinds.Rules, LEGACY_FILE_ID)).flat();
+    expect(roles.map((r) => r.toBase58())).to.deep.equal([rolePDA.toBase58()]);
+    expect(rules.map((r) => r.toBase58())).to.deep.equal([rulePDA.toBase58()]);
+    file = await PROGRAM.account.file.fetch(legacyFilePDA);
+    expect(file.rolesCount).to.equal(1);
+    expect(file.rulesCount).to.equal(1);
+    expect(Buffer.from(file.policyHash)).to.deep.equal(
+      await policy_hash(LEGACY_FILE_ID)
+    );
+    expect(Buffer.from(file.policyHash)).to.not.deep.equal(Buffer.alloc(32));
+
+    await PROGRAM.methods
+      .completeMigration()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        authority,
+        solGatewayFile: legacyFilePDA,
+      })
+      .signers([LEGACY_AUTHORITY])
+      .rpc();
+    file = await PROGRAM.account.file.fetch(legacyFilePDA);
+    expect(file.migrating).to.be.false;
+  });
+
+  it("Delete file with Rules through teardown", async () => {
+    const fileIdToDelete = anchor.web3.Keypair.generate().publicKey;
+    const filePDAToDelete = await file_pda(fileIdToDelete);
+    const deleteAccounts = {
+      ...EVENT_ACCOUNTS,
+      file: filePDAToDelete,
+      signer: PROVIDER.wallet.publicKey,
+      collector: PROVIDER.wallet.publicKey,
+      solGatewayRole: null,
+      solGatewayRule: null,
+      solGatewayToken: null,
+      solGatewayMetadata: null,
+      solGatewaySeed: null,
+    };
+
+    await PROGRAM.methods
+      .initializeFiles({
+        id: fileIdToDelete,
+        recovery: RECOVERY_KEYPAIR.publicKey,
+        name: "file_teardown",
+        cached: false,
+        size: new anchor.BN(1048576), // 1 MB
+        checksum: "123456789abcdef0123456789abcdef0",
+        metadata: null,
+        schema: null,
+        expiresAt: new anchor.BN(Math.floor(Date.now() / 1000) + 31536000),
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDAToDelete,
+        metadataSchema: null,
+        fileMetadata: null,
+      })
+      .rpc();
+
+    const rulePDA = await rule_pda(
+      "role",
+      "resource",
+      "permission",
+      namespaces.Rule,
+      fileIdToDelete
+    );
+    const registryAccounts = await registry_accounts(
+      registryKinds.Rules,
+      fileIdToDelete
+    );
+    await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.Rule,
+        role: "role",
+        resource: "resource",
+        permission: "permission",
+        permissions: new anchor.BN(0),
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...registryAccounts,
+        rule: rulePDA,
+        solGatewayFile: filePDAToDelete,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+
+    // Forcing the deletion marks the File as deleted
+    await PROGRAM.methods.deleteFile(true).accounts(deleteAccounts).rpc();
+    let file = await PROGRAM.account.file.fetch(filePDAToDelete);
+    expect(file.deletedAt).to.not.be.null;
+    expect(file.childrenCount).to.equal(2); // Registry and its first page
+
+    // The File still has a Rule
+    try {
+      await PROGRAM.methods.deleteFile(true).accounts(deleteAccounts).rpc();
+      throw new Error("Deleted files with Rules shouldn't be closed!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("FileNotEmpty");
+    }
+
+    // Anyone can close the accounts of a deleted File, returning the rent to their payers
+    await safe_airdrop(
+      PROVIDER.connection,
+      ANOTHER_WALLET.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    try {
+      await PROGRAM.methods
+        .closeFileAccounts()
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          signer: ANOTHER_WALLET.publicKey,
+          file: filePDAToDelete,
+          collector: PROVIDER.wallet.publicKey,
+        })
+        .remainingAccounts(
+          [rulePDA, ANOTHER_WALLET.publicKey].map((pubkey) => ({
+            pubkey,
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([ANOTHER_WALLET])
+        .rpc();
+      throw new Error("Rent should only be returned to the payer!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("InvalidRentReceiver");
+    }
+    await PROGRAM.methods
+      .closeFileAccounts()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        signer: ANOTHER_WALLET.publicKey,
+        file: filePDAToDelete,
+        collector: PROVIDER.wallet.publicKey,
+      })
+      .remainingAccounts(
+        [
+          rulePDA,
+          registryAccounts.registry,
+          registryAccounts.registryPage,
+        ].flatMap((pubkey) => [
+          { pubkey, isWritable: true, isSigner: false },
+          { pubkey: PROVIDER.wallet.publicKey, isWritable: true, isSigner: false },
+        ])
+      )
+      .signers([ANOTHER_WALLET])
+      .rpc();
+    file = await PROGRAM.account.file.fetch(filePDAToDelete);
+    expect(file.rulesCount).to.equal(0);
+    expect(file.childrenCount).to.equal(0);
+    expect(await PROGRAM.account.rule.fetchNullable(rulePDA)).to.be.null;
+    expect(
+      await PROGRAM.account.policyRegistry.fetchNullable(
+        await registry_pda(fileIdToDelete)
+      )
+    ).to.be.null;
+    expect(
+      await PROGRAM.account.registryPage.fetchNullable(
+        await registry_page_pda(registryKinds.Rules, 0, fileIdToDelete)
+      )
+    ).to.be.null;
+
+    await PROGRAM.methods.deleteFile(false).accounts(deleteAccounts).rpc();
+    expect(await PROGRAM.account.file.fetchNullable(filePDAToDelete)).to.be
+      .null;
+  });
+
+  it("Only the program authority can pause the program", async () => {
+    try {
+      await PROGRAM.methods
+        .setPause(true, false)
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          authority: PROVIDER.wallet.publicKey,
+          config: await config_pda(),
+        })
+        .rpc();
+      throw new Error("Only the program authority should pause the program!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("Unauthorized");
+    }
+    // Not paused while the config was never initialized
+    expect(
+      await PROGRAM.account.programConfig.fetchNullable(await config_pda())
+    ).to.be.null;
+  });
 });
@@ -1,6 +1,24 @@
+import * as anchor from "@project-serum/anchor";
 import { expect } from "chai";
-import { file_pda, WRITE_PERM, rule_pda, READ_PERM } from "./common";
-import { FILE_ID, PROGRAM, PROVIDER, namespaces } from "./constants";
+import {
+  EVENT_ACCOUNTS,
+  cpi_events,
+  file_pda,
+  WRITE_PERM,
+  rule_pda,
+  READ_PERM,
+  permissions_pda,
+  registry_accounts,
+  unregister_accounts,
+  registry_pages,
+} from "./common";
+import {
+  FILE_ID,
+  PROGRAM,
+  PROVIDER,
+  namespaces,
+  registryKinds,
+} from "./constants";
 
 describe("2.- Rules", () => {
   let filePDA = null; // Populated on before() block
@@ -14,40 +32,68 @@ describe("2.- Rules", () => {
     rule1PDA = await rule_pda(role1, resource1, permission1);
   });
 
+  // Updates the provided File fields, keeping the current value of the others
+  const updateFile = async (changes: object) => {
+    const file = await PROGRAM.account.file.fetch(filePDA);
+    await PROGRAM.methods
+      .updateFile({
+        authority: file.authority,
+        recovery: file.recovery,
+        name: file.name,
+        cached: file.cached,
+        fee: file.fee,
+        size: file.size,
+        checksum: file.checksum,
+        accountType: file.accountType,
+        expiresAt: file.expiresAt,
+        maxRoles: file.maxRoles,
+        maxRules: file.maxRules,
+        ...changes,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+  };
+
   it("Add rule", async () => {
-    let listener = null;
-    let [event, _]: any = await new Promise((resolve, reject) => {
-      listener = PROGRAM.addEventListener("RulesChanged", (event, slot) => {
-        PROGRAM.removeEventListener(listener);
-        resolve([event, slot]);
-      });
-      PROGRAM.methods
-        .addRule({
-          namespace: namespaces.Rule,
-          role: role1,
-          resource: resource1,
-          permission: permission1,
-          expiresAt: null,
-        })
-        .accounts({
-          rule: rule1PDA,
-          solGatewayFile: filePDA,
-          solGatewayRole: null,
-          solGatewayRule: null,
-          solGatewayRule2: null,
-          solGatewayToken: null,
-          solGatewayMetadata: null,
-          solGatewaySeed: null,
-        })
-        .rpc();
-      // Break infinite loop in case it fails:
-      setTimeout(() => {
-        reject(new Error("Failed to add rule"));
-      }, 2000);
-    });
+    const signature = await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.Rule,
+        role: role1,
+        resource: resource1,
+        permission: permission1,
+        permissions: new anchor.BN(0),
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
+        rule: rule1PDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc({ commitment: "confirmed" });
+    const [event]: any = await cpi_events(signature, "RulesChanged");
     let rule = await PROGRAM.account.rule.fetch(rule1PDA);
     expect(rule.fileId.toBase58()).to.equal(FILE_ID.toBase58());
     expect(rule.fileId.toBase58()).to.equal(event.fileId.toBase58());
+    expect(event.action).to.deep.equal({ created: {} });
+    expect(event.rule.toBase58()).to.equal(rule1PDA.toBase58());
+    expect(event.role).to.equal(role1);
+    expect(event.actor.toBase58()).to.equal(PROVIDER.wallet.publicKey.toBase58());
     expect(rule.role).to.equal(role1);
     expect(rule.resource).to.equal(resource1);
     expect(rule.permission).to.equal(permission1);
@@ -59,15 +105,19 @@ describe("2.- Rules", () => {
         role: WRITE_PERM.role,
         resource: WRITE_PERM.resource,
         permission: WRITE_PERM.permission,
+        permissions: new anchor.BN(0),
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
         rule: await rule_pda(
           WRITE_PERM.role,
           WRITE_PERM.resource,
           WRITE_PERM.permission
         ),
         solGatewayFile: filePDA,
+        permissionRegistry: null,
         solGatewayRole: null,
         solGatewayRule: null,
         solGatewayRule2: null,
@@ -84,15 +134,95 @@ describe("2.- Rules", () => {
         role: READ_PERM.role,
         resource: READ_PERM.resource,
         permission: READ_PERM.permission,
+        permissions: new anchor.BN(0),
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
         rule: await rule_pda(
           READ_PERM.role,
           READ_PERM.resource,
           READ_PERM.permission
         ),
         solGatewayFile: filePDA,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+  });
+
+  it("Add rule with long namespaced names", async () => {
+    const role = "billing:InvoiceApprover";
+    const resource = "billing:invoices/*";
+    const permission = "Approve";
+    const rulePDA = await rule_pda(role, resource, permission);
+    await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.Rule,
+        role: role,
+        resource: resource,
+        permission: permission,
+        permissions: new anchor.BN(0),
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
+        rule: rulePDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+    let rule = await PROGRAM.account.rule.fetch(rulePDA);
+    expect(rule.role).to.equal(role);
+    expect(rule.resource).to.equal(resource);
+  });
+
+  it("Add rule with a permission set", async () => {
+    const permissionsPDA = await permissions_pda();
+    await PROGRAM.methods
+      .addPermissions({ permissions: ["Read", "Write", "Delete"] })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        permissionRegistry: permissionsPDA,
+      })
+      .rpc();
+    // The registry is sized to its permissions
+    const registryInfo = await PROVIDER.connection.getAccountInfo(
+      permissionsPDA
+    );
+    expect(registryInfo.data.length).to.equal(
+      8 + 32 + 4 + (4 + 4) + (4 + 5) + (4 + 6) + 1 + 32
+    );
+    const rulePDA = await rule_pda("Editor", "Documents", "");
+    await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.Rule,
+        role: "Editor",
+        resource: "Documents",
+        permission: "",
+        permissions: new anchor.BN(0b111), // Read, Write & Delete
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
+        rule: rulePDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: permissionsPDA,
         solGatewayRole: null,
         solGatewayRule: null,
         solGatewayRule2: null,
@@ -101,15 +231,49 @@ describe("2.- Rules", () => {
         solGatewaySeed: null,
       })
       .rpc();
+    let rule = await PROGRAM.account.rule.fetch(rulePDA);
+    expect(rule.permission).to.equal("");
+    expect(rule.permissions.toNumber()).to.equal(0b111);
+  });
+
+  it("Update rule", async () => {
+    const rulePDA = await rule_pda("Editor", "Documents", "");
+    const oneDayLater = Math.floor(new Date().getTime() / 1000) + 24 * 60 * 60;
+    const signature = await PROGRAM.methods
+      .updateRule({ expiresAt: new anchor.BN(oneDayLater) })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        rule: rulePDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: await permissions_pda(),
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc({ commitment: "confirmed" });
+    const [event]: any = await cpi_events(signature, "RulesChanged");
+    expect(event.action).to.deep.equal({ updated: {} });
+    expect(event.rule.toBase58()).to.equal(rulePDA.toBase58());
+    const rule = await PROGRAM.account.rule.fetch(rulePDA);
+    expect(rule.expiresAt.toNumber()).to.equal(oneDayLater);
+    // Only the expiration changes
+    expect(rule.role).to.equal("Editor");
+    expect(rule.permissions.toNumber()).to.equal(0b111);
   });
 
   it("Delete rule", async () => {
     await PROGRAM.methods
       .deleteRule()
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await unregister_accounts(registryKinds.Rules, rule1PDA)),
         rule: rule1PDA,
         collector: PROVIDER.wallet.publicKey,
         solGatewayFile: filePDA,
+        permissionRegistry: null,
         solGatewayRole: null,
         solGatewayRule: null,
         solGatewayRule2: null,
@@ -126,5 +290,94 @@ describe("2.- Rules", () => {
         "Account does not exist or has no data"
       );
     }
+
+    // The rule is no longer listed on the File registry
+    const rules = (await registry_pages(registryKinds.Rules)).flat();
+    const writeRulePDA = await rule_pda(
+      WRITE_PERM.role,
+      WRITE_PERM.resource,
+      WRITE_PERM.permission
+    );
+    expect(rules.some((rule) => rule.equals(rule1PDA))).to.be.false;
+    expect(rules.some((rule) => rule.equals(writeRulePDA))).to.be.true;
+  });
+
+  it("Rule changes bump the cache timestamp of cached files", async () => {
+    const cachedRulePDA = await rule_pda(role1, "Cached", permission1);
+
+    await updateFile({ cached: true });
+    const before = await PROGRAM.account.file.fetch(filePDA);
+    await new Promise((resolve) => setTimeout(resolve, 1500));
+    await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.Rule,
+        role: role1,
+        resource: "Cached",
+        permission: permission1,
+        permissions: new anchor.BN(0),
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
+        rule: cachedRulePDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+    const after = await PROGRAM.account.file.fetch(filePDA);
+    expect(after.rulesUpdatedAt.gt(before.rulesUpdatedAt)).to.be.true;
+    expect(after.rolesUpdatedAt.toNumber()).to.equal(
+      before.rolesUpdatedAt.toNumber()
+    );
+    await updateFile({ cached: false });
+  });
+
+  it("Rules are counted and limited by the File maximum", async () => {
+    let file = await PROGRAM.account.file.fetch(filePDA);
+    const rules = (await registry_pages(registryKinds.Rules)).flat();
+    expect(file.rulesCount).to.equal(rules.length);
+
+    await updateFile({ maxRules: file.rulesCount });
+    try {
+      await PROGRAM.methods
+        .addRule({
+          namespace: namespaces.Rule,
+          role: role1,
+          resource: "Limited",
+          permission: permission1,
+          permissions: new anchor.BN(0),
+          expiresAt: null,
+        })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Rules)),
+          rule: await rule_pda(role1, "Limited", permission1),
+          solGatewayFile: filePDA,
+          permissionRegistry: null,
+          solGatewayRole: null,
+          solGatewayRule: null,
+          solGatewayRule2: null,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: null,
+        })
+        .rpc();
+      throw new Error("Rules over the File maximum shouldn't be allowed!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("TooManyRules");
+    }
+    await updateFile({ maxRules: null });
+    file = await PROGRAM.account.file.fetch(filePDA);
+    expect(file.maxRules).to.be.null;
   });
 });
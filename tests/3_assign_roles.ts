@@ -1,6 +1,21 @@
 import { BN } from "bn.js";
 import { expect } from "chai";
-import { file_pda, role_pda, WRITE_PERM, READ_PERM } from "./common";
+import {
+  EVENT_ACCOUNTS,
+  cpi_events,
+  file_pda,
+  role_pda,
+  WRITE_PERM,
+  READ_PERM,
+  registry_accounts,
+  wallet_roles_pda,
+  registry_pages,
+  unregister_accounts,
+  role_template_pda,
+  claim_pda,
+  request_pda,
+  safe_airdrop,
+} from "./common";
 import {
   addressType,
   FILE_ID,
@@ -8,6 +23,9 @@ import {
   PROVIDER,
   ALLOWED_WALLET,
   ANOTHER_WALLET,
+  METAPLEX,
+  PROVIDER_WALLET,
+  registryKinds,
 } from "./constants";
 import * as anchor from "@project-serum/anchor";
 
@@ -21,40 +39,84 @@ describe("3.- Assign roles", () => {
   it("Assign role to File", async () => {
     const rolePDA = await role_pda(WRITE_PERM.role, PROVIDER.wallet.publicKey);
     const oneHourLater = Math.floor(new Date().getTime() / 1000) + 60 * 60;
-    let listener = null;
-    let [event, _]: any = await new Promise((resolve, reject) => {
-      listener = PROGRAM.addEventListener("RolesChanged", (event, slot) => {
-        PROGRAM.removeEventListener(listener);
-        resolve([event, slot]);
-      });
-      PROGRAM.methods
-        .assignRole({
-          address: PROVIDER.wallet.publicKey,
-          role: WRITE_PERM.role,
-          addressType: addressType.Wallet,
-          expiresAt: new BN(oneHourLater),
-        })
-        .accounts({
-          role: rolePDA,
-          solGatewayFile: filePDA,
-          solGatewayRole: null,
-          solGatewayRule: null,
-          solGatewayToken: null,
-          solGatewayMetadata: null,
-          solGatewaySeed: null,
-        })
-        .rpc();
-      setTimeout(() => {
-        reject(new Error("Failed to assign role"));
-      }, 2000);
-    });
+    const signature = await PROGRAM.methods
+      .assignRole({
+        address: PROVIDER.wallet.publicKey,
+        role: WRITE_PERM.role,
+        addressType: addressType.Wallet,
+        expiresAt: new BN(oneHourLater),
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: await wallet_roles_pda(PROVIDER.wallet.publicKey),
+        role: rolePDA,
+        solGatewayFile: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc({ commitment: "confirmed" });
+    const [event]: any = await cpi_events(signature, "RolesChanged");
 
     const role = await PROGRAM.account.role.fetch(rolePDA);
     expect(FILE_ID.toBase58()).to.equal(event.fileId.toBase58());
+    expect(event.action).to.deep.equal({ created: {} });
+    expect(event.role.toBase58()).to.equal(rolePDA.toBase58());
+    expect(event.roleName).to.equal(WRITE_PERM.role);
+    expect(event.address.toBase58()).to.equal(
+      PROVIDER.wallet.publicKey.toBase58()
+    );
 
     expect(role.role).to.equal(WRITE_PERM.role);
     expect(role.addressType).to.deep.equal(addressType.Wallet);
     expect(role.expiresAt.toNumber()).to.equal(oneHourLater);
+    expect(role.registered).to.be.true;
+  });
+
+  it("Registered roles can't be registered again", async () => {
+    const rolePDA = await role_pda(WRITE_PERM.role, PROVIDER.wallet.publicKey);
+    try {
+      await PROGRAM.methods
+        .registerRole()
+        .accounts({
+          ...(await registry_accounts(registryKinds.Roles)),
+          walletRoles: await wallet_roles_pda(PROVIDER.wallet.publicKey),
+          role: rolePDA,
+          solGatewayFile: filePDA,
+        })
+        .rpc();
+      throw new Error("Registered roles shouldn't be registered again!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("AlreadyRegistered");
+    }
+  });
+
+  it("Update assigned role", async () => {
+    const rolePDA = await role_pda(WRITE_PERM.role, PROVIDER.wallet.publicKey);
+    const twoHoursLater = Math.floor(new Date().getTime() / 1000) + 2 * 60 * 60;
+    await PROGRAM.methods
+      .updateRole({
+        expiresAt: new BN(twoHoursLater),
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        role: rolePDA,
+        solGatewayFile: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+    const role = await PROGRAM.account.role.fetch(rolePDA);
+    expect(role.expiresAt.toNumber()).to.equal(twoHoursLater);
   });
 
   // TODO: fix this test
@@ -68,6 +130,9 @@ describe("3.- Assign roles", () => {
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: await wallet_roles_pda(ALLOWED_WALLET.publicKey),
         role: rolePDA,
         solGatewayFile: filePDA,
         solGatewayRole: null,
@@ -80,11 +145,6 @@ describe("3.- Assign roles", () => {
   });
 
   it("Assign role to Wallet (w/ different rent payer)", async () => {
-    let listener = PROGRAM.addEventListener("RolesChanged", (event, slot) => {
-      expect(FILE_ID.toBase58()).to.equal(event.fileId.toBase58());
-      PROGRAM.removeEventListener(listener);
-    });
-
     const permissionedWallet = anchor.web3.Keypair.generate();
     const rolePDA = await role_pda(
       WRITE_PERM.role,
@@ -100,6 +160,9 @@ describe("3.- Assign roles", () => {
         expiresAt: new BN(oneHourLater),
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: await wallet_roles_pda(permissionedWallet.publicKey),
         role: rolePDA,
         solGatewayFile: filePDA,
         solGatewayRole: null,
@@ -126,6 +189,8 @@ describe("3.- Assign roles", () => {
     // TODO: Check if this is the correct way to send a raw transaction
     const txResponse = await PROVIDER.connection.sendRawTransaction(rawTx);
     await PROVIDER.connection.confirmTransaction(txResponse, "confirmed");
+    const [event]: any = await cpi_events(txResponse, "RolesChanged");
+    expect(event.fileId.toBase58()).to.equal(FILE_ID.toBase58());
 
     const role = await PROGRAM.account.role.fetch(rolePDA);
 
@@ -149,6 +214,50 @@ describe("3.- Assign roles", () => {
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: null,
+        role: rolePDA,
+        solGatewayFile: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+  });
+
+  it("Registries list the File Roles and the Roles of each wallet", async () => {
+    const rolePDA = await role_pda(WRITE_PERM.role, ALLOWED_WALLET.publicKey);
+    const roles = (await registry_pages(registryKinds.Roles)).flat();
+    expect(roles.some((role) => role.equals(rolePDA))).to.be.true;
+    // Roles without address (wildcard) are also listed
+    const readRolePDA = await role_pda(READ_PERM.role, null);
+    expect(roles.some((role) => role.equals(readRolePDA))).to.be.true;
+    const walletRoles = await PROGRAM.account.walletRoles.fetch(
+      await wallet_roles_pda(ALLOWED_WALLET.publicKey)
+    );
+    expect(walletRoles.roles.map((role) => role.toBase58())).to.deep.equal([
+      rolePDA.toBase58(),
+    ]);
+  });
+
+  it("Role holder can renounce its own Role", async () => {
+    const leavingRole = "leaving";
+    const rolePDA = await role_pda(leavingRole, ANOTHER_WALLET.publicKey);
+    const walletRolesPDA = await wallet_roles_pda(ANOTHER_WALLET.publicKey);
+    await PROGRAM.methods
+      .assignRole({
+        address: ANOTHER_WALLET.publicKey,
+        role: leavingRole,
+        addressType: addressType.Wallet,
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: walletRolesPDA,
         role: rolePDA,
         solGatewayFile: filePDA,
         solGatewayRole: null,
@@ -158,5 +267,267 @@ describe("3.- Assign roles", () => {
         solGatewaySeed: null,
       })
       .rpc();
+    const renounceAccounts = {
+      ...EVENT_ACCOUNTS,
+      ...(await unregister_accounts(registryKinds.Roles, rolePDA)),
+      walletRoles: walletRolesPDA,
+      role: rolePDA,
+      solGatewayFile: filePDA,
+      collector: PROVIDER.wallet.publicKey,
+    };
+
+    // Only the wallet holding the Role can renounce it
+    try {
+      await PROGRAM.methods
+        .renounceRole()
+        .accounts({ ...renounceAccounts, signer: PROVIDER.wallet.publicKey })
+        .rpc();
+      throw new Error("Only the role holder should renounce the role!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("NotRoleHolder");
+    }
+
+    // Rent goes back to the account that paid for the Role
+    const role = await PROGRAM.account.role.fetch(rolePDA);
+    expect(role.payer.toBase58()).to.equal(PROVIDER.wallet.publicKey.toBase58());
+    try {
+      await PROGRAM.methods
+        .renounceRole()
+        .accounts({
+          ...renounceAccounts,
+          signer: ANOTHER_WALLET.publicKey,
+          collector: ANOTHER_WALLET.publicKey,
+        })
+        .signers([ANOTHER_WALLET])
+        .rpc();
+      throw new Error("Rent should only be collected by the Role payer!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("Unauthorized");
+    }
+
+    await PROGRAM.methods
+      .renounceRole()
+      .accounts({ ...renounceAccounts, signer: ANOTHER_WALLET.publicKey })
+      .signers([ANOTHER_WALLET])
+      .rpc();
+    expect(await PROGRAM.account.role.fetchNullable(rolePDA)).to.be.null;
+    const walletRoles = await PROGRAM.account.walletRoles.fetch(walletRolesPDA);
+    expect(walletRoles.roles.some((role) => role.equals(rolePDA))).to.be.false;
+  });
+
+  it("File authority manages the Role templates claimed by collection holders", async () => {
+    const collection = anchor.web3.Keypair.generate().publicKey;
+    const templatePDA = await role_template_pda(WRITE_PERM.role, collection);
+    const setTemplate = (duration: number) =>
+      PROGRAM.methods
+        .setRoleTemplate({
+          role: WRITE_PERM.role,
+          collection,
+          duration: new BN(duration),
+        })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          authority: PROVIDER.wallet.publicKey,
+          file: filePDA,
+          roleTemplate: templatePDA,
+        })
+        .rpc();
+
+    try {
+      await setTemplate(0);
+      throw new Error("Templates without duration shouldn't be created!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("InvalidRoleDuration");
+    }
+
+    await setTemplate(60 * 60);
+    await setTemplate(24 * 60 * 60);
+    const template = await PROGRAM.account.roleTemplate.fetch(templatePDA);
+    expect(template.role).to.equal(WRITE_PERM.role);
+    expect(template.collection.toBase58()).to.equal(collection.toBase58());
+    expect(template.duration.toNumber()).to.equal(24 * 60 * 60);
+
+    await PROGRAM.methods
+      .deleteRoleTemplate()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        authority: PROVIDER.wallet.publicKey,
+        file: filePDA,
+        roleTemplate: templatePDA,
+        collector: PROVIDER.wallet.publicKey,
+      })
+      .rpc();
+    expect(await PROGRAM.account.roleTemplate.fetchNullable(templatePDA)).to.be
+      .null;
+  });
+
+  it("Holders claim the Role of a template again once it has expired", async () => {
+    const claimedRole = "claimed";
+    const { nft: collection } = await METAPLEX.nfts().create({
+      uri: "",
+      name: "Claim collection",
+      sellerFeeBasisPoints: 0,
+      isCollection: true,
+    });
+    const mintToken = () =>
+      METAPLEX.nfts().create({
+        uri: "",
+        name: "Claim token",
+        sellerFeeBasisPoints: 0,
+        collection: collection.address,
+        collectionAuthority: PROVIDER_WALLET.payer,
+      });
+    const token = await mintToken();
+    const otherToken = await mintToken();
+    const templatePDA = await role_template_pda(claimedRole, collection.address);
+    await PROGRAM.methods
+      .setRoleTemplate({
+        role: claimedRole,
+        collection: collection.address,
+        duration: new BN(1),
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        authority: PROVIDER.wallet.publicKey,
+        file: filePDA,
+        roleTemplate: templatePDA,
+      })
+      .rpc();
+
+    const rolePDA = await role_pda(claimedRole, PROVIDER.wallet.publicKey);
+    const claimRole = async (minted) =>
+      PROGRAM.methods
+        .claimRole()
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Roles)),
+          walletRoles: await wallet_roles_pda(PROVIDER.wallet.publicKey),
+          solGatewayFile: filePDA,
+          roleTemplate: templatePDA,
+          role: rolePDA,
+          token: minted.tokenAddress,
+          metadata: minted.metadataAddress,
+          claim: await claim_pda(templatePDA, minted.mintAddress),
+        })
+        .rpc({ commitment: "confirmed" });
+
+    await claimRole(token);
+    const rolesCount = (await PROGRAM.account.file.fetch(filePDA)).rolesCount;
+
+    // Another token can't renew the Role while it is active
+    try {
+      await claimRole(otherToken);
+      throw new Error("Active Roles shouldn't be claimed again!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("RoleNotExpired");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    const signature = await claimRole(token);
+    const [event]: any = await cpi_events(signature, "RolesChanged");
+    expect(event.action).to.deep.equal({ updated: {} });
+    const role = await PROGRAM.account.role.fetch(rolePDA);
+    expect(role.expiresAt.toNumber()).to.be.greaterThan(
+      Math.floor(new Date().getTime() / 1000) - 5
+    );
+    // The renewed Role is not counted, registered or listed twice
+    const file = await PROGRAM.account.file.fetch(filePDA);
+    expect(file.rolesCount).to.equal(rolesCount);
+    const walletRoles = await PROGRAM.account.walletRoles.fetch(
+      await wallet_roles_pda(PROVIDER.wallet.publicKey)
+    );
+    expect(walletRoles.roles.filter((r) => r.equals(rolePDA))).to.have.length(1);
+    const registered = (await registry_pages(registryKinds.Roles)).flat();
+    expect(registered.filter((r) => r.equals(rolePDA))).to.have.length(1);
+  });
+
+  it("Requested Roles are assigned once approved", async () => {
+    const requestedRole = "requested";
+    const requestPDA = await request_pda(requestedRole, ANOTHER_WALLET.publicKey);
+    const rolePDA = await role_pda(requestedRole, ANOTHER_WALLET.publicKey);
+    const oneDayLater = Math.floor(new Date().getTime() / 1000) + 24 * 60 * 60;
+    const requestRole = () =>
+      PROGRAM.methods
+        .requestRole({
+          role: requestedRole,
+          justification: "Joining the billing team",
+          expiresAt: new BN(oneDayLater),
+        })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          signer: ANOTHER_WALLET.publicKey,
+          solGatewayFile: filePDA,
+          request: requestPDA,
+        })
+        .signers([ANOTHER_WALLET])
+        .rpc();
+    await safe_airdrop(PROVIDER.connection, ANOTHER_WALLET.publicKey);
+
+    // The requesting wallet can cancel its own request
+    await requestRole();
+    const request = await PROGRAM.account.roleRequest.fetch(requestPDA);
+    expect(request.wallet.toBase58()).to.equal(
+      ANOTHER_WALLET.publicKey.toBase58()
+    );
+    expect(request.justification).to.equal("Joining the billing team");
+    await PROGRAM.methods
+      .rejectRequest()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        signer: ANOTHER_WALLET.publicKey,
+        request: requestPDA,
+        requester: ANOTHER_WALLET.publicKey,
+        solGatewayFile: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .signers([ANOTHER_WALLET])
+      .rpc();
+    expect(await PROGRAM.account.roleRequest.fetchNullable(requestPDA)).to.be
+      .null;
+
+    await requestRole();
+    const signature = await PROGRAM.methods
+      .approveRequest()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: await wallet_roles_pda(ANOTHER_WALLET.publicKey),
+        request: requestPDA,
+        requester: ANOTHER_WALLET.publicKey,
+        role: rolePDA,
+        solGatewayFile: filePDA,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc({ commitment: "confirmed" });
+    const [event]: any = await cpi_events(signature, "RoleRequestChanged");
+    expect(event.action).to.deep.equal({ approved: {} });
+    expect(await PROGRAM.account.roleRequest.fetchNullable(requestPDA)).to.be
+      .null;
+    const role = await PROGRAM.account.role.fetch(rolePDA);
+    expect(role.role).to.equal(requestedRole);
+    expect(role.address.toBase58()).to.equal(
+      ANOTHER_WALLET.publicKey.toBase58()
+    );
+    expect(role.expiresAt.toNumber()).to.equal(oneDayLater);
   });
 });
@@ -1,10 +1,15 @@
+import * as anchor from "@project-serum/anchor";
 import {
+  EVENT_ACCOUNTS,
+  audit_pda,
   file_pda,
+  return_data,
   role_pda,
   WRITE_PERM,
   rule_pda,
   READ_PERM,
   seed_pda,
+  safe_airdrop,
 } from "./common";
 import {
   FILE_ID,
@@ -59,9 +64,11 @@ describe("4.- Check permissions", () => {
         solGatewayFile: filePDA,
         solGatewayRule: writeRulePDA,
         solGatewayRole: null,
+        solGatewayPermissions: null,
         solGatewayToken: null,
         solGatewayMetadata: null,
         solGatewaySeed: null,
+        solGatewayAudit: null,
       });
     const recentBlockhash = await PROVIDER.connection.getLatestBlockhash();
     const fee = await new Transaction({
@@ -98,10 +105,12 @@ describe("4.- Check permissions", () => {
       .accounts({
         solGatewayFile: filePDA,
         solGatewayRole: rolePDA,
+        solGatewayPermissions: null,
         solGatewayRule: writeRulePDA,
         solGatewayToken: null,
         solGatewayMetadata: null,
         solGatewaySeed: walletSeedPDA,
+        solGatewayAudit: null,
         signer: ALLOWED_WALLET.publicKey,
       })
       .signers([ALLOWED_WALLET]);
@@ -139,10 +148,12 @@ describe("4.- Check permissions", () => {
       .accounts({
         solGatewayFile: filePDA,
         solGatewayRole: await role_pda(READ_PERM.role, null), // Null address represents the wildcard "*"
+        solGatewayPermissions: null,
         solGatewayRule: readRulePDA,
         solGatewayToken: null,
         solGatewayMetadata: null,
         solGatewaySeed: walletSeedPDA,
+        solGatewayAudit: null,
         signer: ALLOWED_WALLET.publicKey,
       })
       .signers([ALLOWED_WALLET])
@@ -159,4 +170,161 @@ describe("4.- Check permissions", () => {
         .toNumber()
     ).to.equal(FEE);
   });
+
+  it("Record allowed checks on the audit log", async () => {
+    const auditPDA = await audit_pda();
+    const rolePDA = await role_pda(WRITE_PERM.role, ALLOWED_WALLET.publicKey);
+    await PROGRAM.methods
+      .updateAudit(true)
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        auditLog: auditPDA,
+      })
+      .rpc();
+    const allowedAccounts = {
+      solGatewayFile: filePDA,
+      solGatewayRole: rolePDA,
+      solGatewayPermissions: null,
+      solGatewayRule: writeRulePDA,
+      solGatewayToken: null,
+      solGatewayMetadata: null,
+      solGatewaySeed: walletSeedPDA,
+      signer: ALLOWED_WALLET.publicKey,
+    };
+    const allowedRule = {
+      fileId: FILE_ID,
+      namespace: namespaces.Rule,
+      resource: WRITE_PERM.resource,
+      permission: WRITE_PERM.permission,
+    };
+    await PROGRAM.methods
+      .allowed(allowedRule)
+      .accounts({ ...allowedAccounts, solGatewayAudit: auditPDA })
+      .signers([ALLOWED_WALLET])
+      .rpc();
+    const auditLog = await PROGRAM.account.auditLog.fetch(auditPDA);
+    expect(auditLog.fileId.toBase58()).to.equal(FILE_ID.toBase58());
+    expect(auditLog.sequence.toNumber()).to.equal(1);
+    expect(auditLog.entries.length).to.equal(1);
+    expect(auditLog.entries[0].signer.toBase58()).to.equal(
+      ALLOWED_WALLET.publicKey.toBase58()
+    );
+    expect(auditLog.entries[0].namespace).to.equal(namespaces.Rule);
+    expect(auditLog.entries[0].resource).to.equal(WRITE_PERM.resource);
+    expect(auditLog.entries[0].permission).to.equal(WRITE_PERM.permission);
+    expect(auditLog.entries[0].role).to.equal(WRITE_PERM.role);
+
+    // Audit Log account is mandatory while auditing is enabled
+    try {
+      await PROGRAM.methods
+        .allowed(allowedRule)
+        .accounts({ ...allowedAccounts, solGatewayAudit: null })
+        .signers([ALLOWED_WALLET])
+        .rpc();
+      throw new Error("Access checks must be audited when enabled");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("MissingAuditAccount");
+    }
+
+    await PROGRAM.methods
+      .updateAudit(false)
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        auditLog: auditPDA,
+      })
+      .rpc();
+  });
+
+  it("Check access without failing", async () => {
+    const rolePDA = await role_pda(WRITE_PERM.role, ALLOWED_WALLET.publicKey);
+    const accounts = {
+      solGatewayFile: filePDA,
+      solGatewayRole: rolePDA,
+      solGatewayPermissions: null,
+      solGatewayRule: writeRulePDA,
+      solGatewayToken: null,
+      solGatewayMetadata: null,
+      solGatewaySeed: walletSeedPDA,
+      solGatewayAudit: null,
+      signer: ALLOWED_WALLET.publicKey,
+    };
+    const granted = await PROGRAM.methods
+      .check({
+        fileId: FILE_ID,
+        namespace: namespaces.Rule,
+        resource: WRITE_PERM.resource,
+        permission: WRITE_PERM.permission,
+      })
+      .accounts(accounts)
+      .signers([ALLOWED_WALLET])
+      .simulate();
+    let decision = return_data(granted.raw as string[], "AccessDecision");
+    expect(decision.granted).to.equal(true);
+    expect(decision.reason).to.deep.equal({ granted: {} });
+    expect(decision.rule.toBase58()).to.equal(writeRulePDA.toBase58());
+
+    // Denied access is returned instead of failing the transaction
+    const denied = await PROGRAM.methods
+      .check({
+        fileId: FILE_ID,
+        namespace: namespaces.AssignRole,
+        resource: WRITE_PERM.resource,
+        permission: WRITE_PERM.permission,
+      })
+      .accounts(accounts)
+      .signers([ALLOWED_WALLET])
+      .simulate();
+    decision = return_data(denied.raw as string[], "AccessDecision");
+    expect(decision.granted).to.equal(false);
+    expect(decision.reason).to.deep.equal({ namespaceMismatch: {} });
+
+    // A different File is also returned as a denial reason
+    const wrongFile = await PROGRAM.methods
+      .check({
+        fileId: anchor.web3.Keypair.generate().publicKey,
+        namespace: namespaces.Rule,
+        resource: WRITE_PERM.resource,
+        permission: WRITE_PERM.permission,
+      })
+      .accounts(accounts)
+      .signers([ALLOWED_WALLET])
+      .simulate();
+    decision = return_data(wrongFile.raw as string[], "AccessDecision");
+    expect(decision.granted).to.equal(false);
+    expect(decision.reason).to.deep.equal({ invalidFileId: {} });
+  });
+
+  it("Denied checks don't keep the seed account of the signer", async () => {
+    const wallet = anchor.web3.Keypair.generate();
+    await safe_airdrop(PROVIDER.connection, wallet.publicKey);
+    const seedPDA = await seed_pda(wallet.publicKey);
+    const balance = await PROVIDER.connection.getBalance(wallet.publicKey);
+    await PROGRAM.methods
+      .check({
+        fileId: FILE_ID,
+        namespace: namespaces.Rule,
+        resource: WRITE_PERM.resource,
+        permission: WRITE_PERM.permission,
+      })
+      .accounts({
+        solGatewayFile: filePDA,
+        solGatewayRole: null,
+        solGatewayPermissions: null,
+        solGatewayRule: writeRulePDA,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: seedPDA,
+        solGatewayAudit: null,
+        signer: wallet.publicKey,
+      })
+      .signers([wallet])
+      .rpc({ commitment: "confirmed" });
+    expect(await PROVIDER.connection.getAccountInfo(seedPDA)).to.be.null;
+    // Only the transaction fee is paid
+    expect(
+      await PROVIDER.connection.getBalance(wallet.publicKey, "confirmed")
+    ).to.equal(balance - FEE);
+  });
 });
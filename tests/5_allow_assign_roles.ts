@@ -1,10 +1,14 @@
+import * as anchor from "@project-serum/anchor";
 import {
+  EVENT_ACCOUNTS,
   file_pda,
   role_pda,
   WRITE_PERM,
   rule_pda,
   READ_PERM,
   seed_pda,
+  registry_accounts,
+  wallet_roles_pda,
 } from "./common";
 import {
   ANOTHER_WALLET,
@@ -13,8 +17,10 @@ import {
   ALLOWED_WALLET,
   addressType,
   namespaces,
+  registryKinds,
 } from "./constants";
 import { expect } from "chai";
+import { BN } from "bn.js";
 
 describe("5.- Allow assign roles", () => {
   let filePDA = null; // Populated on before() block
@@ -54,6 +60,9 @@ describe("5.- Allow assign roles", () => {
           expiresAt: null,
         })
         .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Roles)),
+          walletRoles: await wallet_roles_pda(ANOTHER_WALLET.publicKey),
           role: anotherWalletRole,
           solGatewayFile: filePDA,
           solGatewayRole: allowedWalletRole,
@@ -70,7 +79,7 @@ describe("5.- Allow assign roles", () => {
       if (!e.hasOwnProperty("error")) {
         throw e;
       }
-      expect(e.error.errorCode.code).to.equal("Unauthorized");
+      expect(e.error.errorCode.code).to.equal("MissingRuleOrRole");
     }
   });
 
@@ -88,11 +97,15 @@ describe("5.- Allow assign roles", () => {
         role: WRITE_PERM.role,
         resource: "Wallet",
         permission: WRITE_PERM.role,
+        permissions: new anchor.BN(0),
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
         rule: rulePDA,
         solGatewayFile: filePDA,
+        permissionRegistry: null,
         solGatewayRole: null,
         solGatewayRule: null,
         solGatewayRule2: null,
@@ -111,6 +124,9 @@ describe("5.- Allow assign roles", () => {
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Roles)),
+        walletRoles: await wallet_roles_pda(ANOTHER_WALLET.publicKey),
         role: anotherWalletRole,
         solGatewayFile: filePDA,
         solGatewayRole: allowedWalletRole,
@@ -134,13 +150,87 @@ describe("5.- Allow assign roles", () => {
       .accounts({
         solGatewayFile: filePDA,
         solGatewayRole: anotherWalletRole,
+        solGatewayPermissions: null,
         solGatewayRule: writeRulePDA,
         solGatewayToken: null,
         solGatewayMetadata: null,
         solGatewaySeed: anotherWalletSeedPDA,
+        solGatewayAudit: null,
         signer: ANOTHER_WALLET.publicKey,
       })
       .signers([ANOTHER_WALLET])
       .rpc();
   });
+
+  it("Wallet allowed to update assigned role", async () => {
+    const oneDayLater = Math.floor(new Date().getTime() / 1000) + 24 * 60 * 60;
+    const updateRole = (rule) =>
+      PROGRAM.methods
+        .updateRole({ expiresAt: new BN(oneDayLater) })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          role: anotherWalletRole,
+          solGatewayFile: filePDA,
+          solGatewayRole: allowedWalletRole,
+          solGatewayRule: rule,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: allowedWalletSeedPDA,
+          signer: ALLOWED_WALLET.publicKey,
+        })
+        .signers([ALLOWED_WALLET])
+        .rpc();
+
+    // Assigning a Role doesn't allow updating it
+    const assignRulePDA = await rule_pda(
+      WRITE_PERM.role,
+      "Wallet",
+      WRITE_PERM.role,
+      namespaces.AssignRole
+    );
+    try {
+      await updateRole(assignRulePDA);
+      throw Error("Wallets shouldn't update roles with AssignRole rules!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("NamespaceMismatch");
+    }
+
+    // Allow role "Authenticated" to update the "Authenticated" role of other wallets
+    const rulePDA = await rule_pda(
+      WRITE_PERM.role,
+      "Wallet",
+      WRITE_PERM.role,
+      namespaces.UpdateAssignRole
+    );
+    await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.UpdateAssignRole,
+        role: WRITE_PERM.role,
+        resource: "Wallet",
+        permission: WRITE_PERM.role,
+        permissions: new anchor.BN(0),
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
+        rule: rulePDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+
+    await updateRole(rulePDA);
+    const role = await PROGRAM.account.role.fetch(anotherWalletRole);
+    expect(role.expiresAt.toNumber()).to.equal(oneDayLater);
+  });
 });
@@ -1,5 +1,21 @@
-import { file_pda, role_pda, WRITE_PERM, rule_pda, seed_pda } from "./common";
-import { PROGRAM, ALLOWED_WALLET, namespaces } from "./constants";
+import * as anchor from "@project-serum/anchor";
+import {
+  EVENT_ACCOUNTS,
+  file_pda,
+  role_pda,
+  WRITE_PERM,
+  rule_pda,
+  seed_pda,
+  policy_hash,
+  registry_accounts,
+} from "./common";
+import {
+  PROGRAM,
+  PROVIDER,
+  ALLOWED_WALLET,
+  namespaces,
+  registryKinds,
+} from "./constants";
 import { expect } from "chai";
 import { PublicKey } from "@metaplex-foundation/js";
 
@@ -43,11 +59,15 @@ describe("6.- Allow add rules", () => {
           role: WRITE_PERM.role,
           resource: newResource,
           permission: allPerms,
+          permissions: new anchor.BN(0),
           expiresAt: null,
         })
         .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Rules)),
           rule: newRulePDA,
           solGatewayFile: filePDA,
+          permissionRegistry: null,
           solGatewayRole: allowedWalletRolePDA,
           solGatewayRule: null,
           solGatewayRule2: null,
@@ -63,7 +83,7 @@ describe("6.- Allow add rules", () => {
       if (!e.hasOwnProperty("error")) {
         throw e;
       }
-      expect(e.error.errorCode.code).to.equal("Unauthorized");
+      expect(e.error.errorCode.code).to.equal("MissingRuleOrRole");
     }
   });
 
@@ -78,11 +98,15 @@ describe("6.- Allow add rules", () => {
         role: WRITE_PERM.role,
         resource: `${namespaces.Rule}`,
         permission: WRITE_PERM.role,
+        permissions: new anchor.BN(0),
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
         rule: nsRoleRulePDA,
         solGatewayFile: filePDA,
+        permissionRegistry: null,
         solGatewayRole: null,
         solGatewayRule: null,
         solGatewayRule2: null,
@@ -104,11 +128,15 @@ describe("6.- Allow add rules", () => {
         role: WRITE_PERM.role,
         resource: newResource,
         permission: allPerms,
+        permissions: new anchor.BN(0),
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
         rule: resourcePermRulePDA,
         solGatewayFile: filePDA,
+        permissionRegistry: null,
         solGatewayRole: null,
         solGatewayRule: null,
         solGatewayRule2: null,
@@ -130,9 +158,12 @@ describe("6.- Allow add rules", () => {
         role: WRITE_PERM.role,
         resource: newResource,
         permission: "Add",
+        permissions: new anchor.BN(0),
         expiresAt: null,
       })
       .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
         rule: await rule_pda(
           WRITE_PERM.role,
           newResource,
@@ -140,6 +171,7 @@ describe("6.- Allow add rules", () => {
           namespaces.Rule
         ),
         solGatewayFile: filePDA,
+        permissionRegistry: null,
         solGatewayRole: allowedWalletRolePDA,
         solGatewayRule: nsRoleRulePDA,
         solGatewayRule2: resourcePermRulePDA,
@@ -160,9 +192,12 @@ describe("6.- Allow add rules", () => {
           role: WRITE_PERM.role,
           resource: WRITE_PERM.resource,
           permission: "Add",
+          permissions: new anchor.BN(0),
           expiresAt: null,
         })
         .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Rules)),
           rule: await rule_pda(
             WRITE_PERM.role,
             WRITE_PERM.resource,
@@ -170,6 +205,7 @@ describe("6.- Allow add rules", () => {
             namespaces.Rule
           ),
           solGatewayFile: filePDA,
+          permissionRegistry: null,
           solGatewayRole: allowedWalletRolePDA,
           solGatewayRule: nsRoleRulePDA,
           solGatewayRule2: resourcePermRulePDA,
@@ -187,7 +223,272 @@ describe("6.- Allow add rules", () => {
       if (!e.hasOwnProperty("error")) {
         throw e;
       }
-      expect(e.error.errorCode.code).to.equal("Unauthorized");
+      expect(e.error.errorCode.code).to.equal("ResourceMismatch");
+    }
+  });
+
+  it("Wallet can update rules of allowed Namespace, Role, Resource and Permission", async () => {
+    const updateNsRoleRulePDA = await rule_pda(
+      WRITE_PERM.role,
+      `${namespaces.Rule}`,
+      WRITE_PERM.role,
+      namespaces.UpdateRuleNSRole
+    );
+    const updateResourcePermRulePDA = await rule_pda(
+      WRITE_PERM.role,
+      newResource,
+      allPerms,
+      namespaces.UpdateRuleResourcePerm
+    );
+    const addRule = async (namespace, resource, permission, rule) =>
+      PROGRAM.methods
+        .addRule({
+          namespace,
+          role: WRITE_PERM.role,
+          resource,
+          permission,
+          permissions: new anchor.BN(0),
+          expiresAt: null,
+        })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          ...(await registry_accounts(registryKinds.Rules)),
+          rule,
+          solGatewayFile: filePDA,
+          permissionRegistry: null,
+          solGatewayRole: null,
+          solGatewayRule: null,
+          solGatewayRule2: null,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: null,
+        })
+        .rpc();
+    const updateRule = (rule, expiresAt) =>
+      PROGRAM.methods
+        .updateRule({ expiresAt: new anchor.BN(expiresAt) })
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          rule,
+          solGatewayFile: filePDA,
+          permissionRegistry: null,
+          solGatewayRole: allowedWalletRolePDA,
+          solGatewayRule: updateNsRoleRulePDA,
+          solGatewayRule2: updateResourcePermRulePDA,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: walletSeedPDA,
+          signer: ALLOWED_WALLET.publicKey,
+        })
+        .signers([ALLOWED_WALLET])
+        .rpc();
+
+    // Namespace "UpdateRuleNSRole" only accepts namespaces as Resource
+    try {
+      await addRule(
+        namespaces.UpdateRuleNSRole,
+        "MyNamespace",
+        WRITE_PERM.role,
+        await rule_pda(
+          WRITE_PERM.role,
+          "MyNamespace",
+          WRITE_PERM.role,
+          namespaces.UpdateRuleNSRole
+        )
+      );
+      throw Error("Rules with invalid namespaces shouldn't be created!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("InvalidNamespace");
+    }
+
+    // Allows the role "Authenticated" to update the rules of namespace Rule & role "Authenticated"
+    // on resource "MyNewResource" (any permission)
+    await addRule(
+      namespaces.UpdateRuleNSRole,
+      `${namespaces.Rule}`,
+      WRITE_PERM.role,
+      updateNsRoleRulePDA
+    );
+    await addRule(
+      namespaces.UpdateRuleResourcePerm,
+      newResource,
+      allPerms,
+      updateResourcePermRulePDA
+    );
+
+    const oneDayLater = Math.floor(new Date().getTime() / 1000) + 24 * 60 * 60;
+    const rulePDA = await rule_pda(
+      WRITE_PERM.role,
+      newResource,
+      "Add",
+      namespaces.Rule
+    );
+    await updateRule(rulePDA, oneDayLater);
+    const rule = await PROGRAM.account.rule.fetch(rulePDA);
+    expect(rule.expiresAt.toNumber()).to.equal(oneDayLater);
+
+    // Rules of other resources can't be updated
+    try {
+      await updateRule(
+        await rule_pda(
+          WRITE_PERM.role,
+          WRITE_PERM.resource,
+          WRITE_PERM.permission,
+          namespaces.Rule
+        ),
+        oneDayLater
+      );
+      throw Error("Wallets shouldn't update rules of other resources!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("ResourceMismatch");
+    }
+  });
+
+  it("Role can be delegated to update the File cache", async () => {
+    const cacheRulePDA = await rule_pda(
+      WRITE_PERM.role,
+      "cache",
+      allPerms,
+      namespaces.UpdateCache
+    );
+    await PROGRAM.methods
+      .addRule({
+        namespace: namespaces.UpdateCache,
+        role: WRITE_PERM.role,
+        resource: "cache",
+        permission: allPerms,
+        permissions: new anchor.BN(0),
+        expiresAt: null,
+      })
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        ...(await registry_accounts(registryKinds.Rules)),
+        rule: cacheRulePDA,
+        solGatewayFile: filePDA,
+        permissionRegistry: null,
+        solGatewayRole: null,
+        solGatewayRule: null,
+        solGatewayRule2: null,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: null,
+      })
+      .rpc();
+
+    const before = await PROGRAM.account.file.fetch(filePDA);
+    await PROGRAM.methods
+      .updateCache(1)
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        file: filePDA,
+        signer: ALLOWED_WALLET.publicKey,
+        solGatewayRole: allowedWalletRolePDA,
+        solGatewayRule: cacheRulePDA,
+        solGatewayToken: null,
+        solGatewayMetadata: null,
+        solGatewaySeed: walletSeedPDA,
+      })
+      .signers([ALLOWED_WALLET])
+      .rpc();
+    const after = await PROGRAM.account.file.fetch(filePDA);
+    expect(after.rulesUpdatedAt.gte(before.rulesUpdatedAt)).to.be.true;
+  });
+
+  it("Frozen File only allows the authority and the given namespaces", async () => {
+    const cacheRulePDA = await rule_pda(
+      WRITE_PERM.role,
+      "cache",
+      allPerms,
+      namespaces.UpdateCache
+    );
+    const updateCache = () =>
+      PROGRAM.methods
+        .updateCache(1)
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          file: filePDA,
+          signer: ALLOWED_WALLET.publicKey,
+          solGatewayRole: allowedWalletRolePDA,
+          solGatewayRule: cacheRulePDA,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: walletSeedPDA,
+        })
+        .signers([ALLOWED_WALLET])
+        .rpc();
+    const freezeFile = (allowedNamespaces: number[]) =>
+      PROGRAM.methods
+        .freezeFile(Buffer.from(allowedNamespaces))
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          signer: PROVIDER.wallet.publicKey,
+          file: filePDA,
+        })
+        .rpc();
+
+    await freezeFile([]);
+    let file = await PROGRAM.account.file.fetch(filePDA);
+    expect(file.frozenAt).to.not.be.null;
+    try {
+      await updateCache();
+      throw new Error("Frozen files shouldn't allow roles!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("FileFrozen");
+    }
+
+    // Namespaces allowed through keep working
+    await freezeFile([namespaces.UpdateCache]);
+    await updateCache();
+
+    await PROGRAM.methods
+      .unfreezeFile()
+      .accounts({
+        ...EVENT_ACCOUNTS,
+        signer: PROVIDER.wallet.publicKey,
+        file: filePDA,
+      })
+      .rpc();
+    file = await PROGRAM.account.file.fetch(filePDA);
+    expect(file.frozenAt).to.be.null;
+    expect(file.frozenNamespaces).to.deep.equal(Buffer.from([]));
+  });
+
+  it("File policy hash matches its Role and Rule change events", async () => {
+    const file = await PROGRAM.account.file.fetch(filePDA);
+    expect(Buffer.from(file.policyHash)).to.deep.equal(await policy_hash());
+  });
+
+  it("File with Roles and Rules can't be deleted without force", async () => {
+    try {
+      await PROGRAM.methods
+        .deleteFile(false)
+        .accounts({
+          ...EVENT_ACCOUNTS,
+          file: filePDA,
+          signer: PROVIDER.wallet.publicKey,
+          collector: PROVIDER.wallet.publicKey,
+          solGatewayRole: null,
+          solGatewayRule: null,
+          solGatewayToken: null,
+          solGatewayMetadata: null,
+          solGatewaySeed: null,
+        })
+        .rpc();
+      throw new Error("Files with Roles or Rules shouldn't be deleted!");
+    } catch (e) {
+      if (!e.hasOwnProperty("error")) {
+        throw e;
+      }
+      expect(e.error.errorCode.code).to.equal("FileNotEmpty");
     }
   });
 });
@@ -1,6 +1,14 @@
 import * as anchor from "@project-serum/anchor";
 import { PublicKey } from "@solana/web3.js";
-import { FILE_ID, METADATA_PROGRAM_ID, PROGRAM, PROVIDER } from "./constants";
+import { createHash } from "crypto";
+import {
+  FILE_ID,
+  METADATA_PROGRAM_ID,
+  PROGRAM,
+  PROVIDER,
+  REGISTRY_PAGE_SIZE,
+  registryKinds,
+} from "./constants";
 
 export async function file_pda(fileId: PublicKey = FILE_ID) {
   return (
@@ -20,26 +28,204 @@ export async function metadata_pda(fileId: PublicKey) {
   )[0];
 }
 
+/**
+ *  Seeds are encoded as: version | (tag | length (u32 LE) | value)* and hashed (SHA-256),
+ *  see `SeedEncoder` on programs/sol-gateway/src/utils/seeds.rs
+ */
+export const SEED_VERSION = 1;
+export enum seedTags {
+  Namespace = 0,
+  Role = 1,
+  Resource = 2,
+  Permission = 3,
+  AddressType = 4,
+  Address = 5,
+  Wildcard = 6,
+}
+
+export function encode_seed(values: [seedTags, Uint8Array][]): Buffer {
+  const hash = createHash("sha256").update(Buffer.from([SEED_VERSION]));
+  for (const [tag, value] of values) {
+    const length = Buffer.alloc(4);
+    length.writeUInt32LE(value.length);
+    hash.update(Buffer.from([tag])).update(length).update(value);
+  }
+  return hash.digest();
+}
+
 export async function rule_pda(
   role,
   resource,
   permission,
-  namespace: number = 0
+  namespace: number = 0,
+  fileId: PublicKey = FILE_ID
 ) {
   return (
     await PublicKey.findProgramAddressSync(
       [
-        new Uint8Array([namespace]),
-        anchor.utils.bytes.utf8.encode(role),
-        anchor.utils.bytes.utf8.encode(resource),
-        anchor.utils.bytes.utf8.encode(permission),
-        FILE_ID.toBuffer(),
+        anchor.utils.bytes.utf8.encode("rule"),
+        encode_seed([
+          [seedTags.Namespace, new Uint8Array([namespace])],
+          [seedTags.Role, anchor.utils.bytes.utf8.encode(role)],
+          [seedTags.Resource, anchor.utils.bytes.utf8.encode(resource)],
+          [seedTags.Permission, anchor.utils.bytes.utf8.encode(permission)],
+        ]),
+        fileId.toBuffer(),
+      ],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+export async function permissions_pda(fileId: PublicKey = FILE_ID) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [anchor.utils.bytes.utf8.encode("permissions"), fileId.toBuffer()],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+export async function schema_pda(fileId: PublicKey = FILE_ID) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [anchor.utils.bytes.utf8.encode("schema"), fileId.toBuffer()],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+export async function audit_pda(fileId: PublicKey = FILE_ID) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [anchor.utils.bytes.utf8.encode("audit"), fileId.toBuffer()],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+export async function config_pda() {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [anchor.utils.bytes.utf8.encode("config")],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+export async function registry_pda(fileId: PublicKey = FILE_ID) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [anchor.utils.bytes.utf8.encode("registry"), fileId.toBuffer()],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+export async function registry_page_pda(
+  kind: registryKinds,
+  page: number,
+  fileId: PublicKey = FILE_ID
+) {
+  const pageIndex = Buffer.alloc(4);
+  pageIndex.writeUInt32LE(page);
+  return (
+    await PublicKey.findProgramAddressSync(
+      [
+        anchor.utils.bytes.utf8.encode("registry"),
+        fileId.toBuffer(),
+        new Uint8Array([kind]),
+        pageIndex,
       ],
       PROGRAM.programId
     )
   )[0];
 }
 
+export async function wallet_roles_pda(
+  wallet: PublicKey,
+  fileId: PublicKey = FILE_ID
+) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [
+        anchor.utils.bytes.utf8.encode("wallet_roles"),
+        fileId.toBuffer(),
+        wallet.toBuffer(),
+      ],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+/**
+ *  Registry accounts required to add a Role or Rule (new entries go to the last page).
+ */
+export async function registry_accounts(
+  kind: registryKinds,
+  fileId: PublicKey = FILE_ID
+) {
+  const registry = await registry_pda(fileId);
+  const data = await PROGRAM.account.policyRegistry.fetchNullable(registry);
+  const count = data ? (kind === registryKinds.Roles ? data.roles : data.rules) : 0;
+  return {
+    registry,
+    registryPage: await registry_page_pda(
+      kind,
+      Math.floor(count / REGISTRY_PAGE_SIZE),
+      fileId
+    ),
+  };
+}
+
+/**
+ *  Registry accounts required to remove a Role or Rule: the page holding it and
+ *  the last page (only when different), whose last entry fills the gap.
+ */
+export async function unregister_accounts(
+  kind: registryKinds,
+  account: PublicKey,
+  fileId: PublicKey = FILE_ID
+) {
+  const registry = await registry_pda(fileId);
+  const data = await PROGRAM.account.policyRegistry.fetchNullable(registry);
+  if (!data) {
+    return { registry, registryPage: null, registryLastPage: null };
+  }
+  const pages = await registry_pages(kind, fileId);
+  const page = pages.findIndex((accounts) =>
+    accounts.some((a) => a.equals(account))
+  );
+  const lastPage = pages.length - 1;
+  return {
+    registry,
+    registryPage: await registry_page_pda(kind, page, fileId),
+    registryLastPage:
+      page === lastPage ? null : await registry_page_pda(kind, lastPage, fileId),
+  };
+}
+
+/**
+ *  Lists the Role or Rule PDAs of a File from its registry pages.
+ */
+export async function registry_pages(
+  kind: registryKinds,
+  fileId: PublicKey = FILE_ID
+): Promise<PublicKey[][]> {
+  const data = await PROGRAM.account.policyRegistry.fetchNullable(
+    await registry_pda(fileId)
+  );
+  const count = data ? (kind === registryKinds.Roles ? data.roles : data.rules) : 0;
+  const pages = [];
+  for (let page = 0; page * REGISTRY_PAGE_SIZE < count; page++) {
+    const registryPage = await PROGRAM.account.registryPage.fetch(
+      await registry_page_pda(kind, page, fileId)
+    );
+    pages.push(registryPage.accounts);
+  }
+  return pages;
+}
+
 export async function seed_pda(signer: PublicKey) {
   return (
     await PublicKey.findProgramAddressSync(
@@ -52,12 +238,43 @@ export async function seed_pda(signer: PublicKey) {
 /**
  *  Empty Addresses are considered wildcards "*" (role will be applied to all users)
  */
-export async function role_pda(role, address: PublicKey | null) {
+export async function role_pda(
+  role,
+  address: PublicKey | null,
+  addressTypeIndex: number = 0, // 0 => Wallet, 1 => Collection
+  fileId: PublicKey = FILE_ID
+) {
   return (
     await PublicKey.findProgramAddressSync(
       [
-        anchor.utils.bytes.utf8.encode(role),
-        address ? address.toBuffer() : anchor.utils.bytes.utf8.encode("*"),
+        anchor.utils.bytes.utf8.encode("role"),
+        encode_seed([
+          [seedTags.Role, anchor.utils.bytes.utf8.encode(role)],
+          [seedTags.AddressType, new Uint8Array([addressTypeIndex])],
+          address
+            ? [seedTags.Address, address.toBuffer()]
+            : [seedTags.Wildcard, new Uint8Array([])],
+        ]),
+        fileId.toBuffer(),
+      ],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+/**
+ *  Template used by the holders of a collection to claim a Wallet Role
+ */
+export async function role_template_pda(role, collection: PublicKey) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [
+        anchor.utils.bytes.utf8.encode("role_template"),
+        encode_seed([
+          [seedTags.Role, anchor.utils.bytes.utf8.encode(role)],
+          [seedTags.AddressType, new Uint8Array([1])], // Collection
+          [seedTags.Address, collection.toBuffer()],
+        ]),
         FILE_ID.toBuffer(),
       ],
       PROGRAM.programId
@@ -65,6 +282,178 @@ export async function role_pda(role, address: PublicKey | null) {
   )[0];
 }
 
+/**
+ *  Claim of a Role template by a token of its collection
+ */
+export async function claim_pda(template: PublicKey, mint: PublicKey) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [
+        anchor.utils.bytes.utf8.encode("claim"),
+        template.toBuffer(),
+        mint.toBuffer(),
+      ],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+/**
+ *  Pending request of a Wallet Role
+ */
+export async function request_pda(role, wallet: PublicKey) {
+  return (
+    await PublicKey.findProgramAddressSync(
+      [
+        anchor.utils.bytes.utf8.encode("request"),
+        encode_seed([
+          [seedTags.Role, anchor.utils.bytes.utf8.encode(role)],
+          [seedTags.AddressType, new Uint8Array([0])], // Wallet
+          [seedTags.Address, wallet.toBuffer()],
+        ]),
+        FILE_ID.toBuffer(),
+      ],
+      PROGRAM.programId
+    )
+  )[0];
+}
+
+/**
+ *  String metadata value (metadata values are typed: string, u64, i64, bool, pubkey or bytes)
+ */
+export function string_value(value: string) {
+  return { string: { 0: value } };
+}
+
+/**
+ *  Accounts required to emit events through self-CPI (Anchor's event-CPI)
+ */
+export const EVENT_ACCOUNTS = {
+  eventAuthority: PublicKey.findProgramAddressSync(
+    [anchor.utils.bytes.utf8.encode("__event_authority")],
+    PROGRAM.programId
+  )[0],
+  program: PROGRAM.programId,
+};
+
+/**
+ *  Events are emitted through self-CPI, so they are decoded from the inner instructions data
+ *  (skipping the 8 bytes event instruction tag).
+ */
+async function program_events(signature: string) {
+  const tx = await PROVIDER.connection.getTransaction(signature, {
+    commitment: "confirmed",
+  });
+  const accountKeys = tx.transaction.message.accountKeys;
+  const events = [];
+  for (const inner of tx.meta.innerInstructions) {
+    for (const ix of inner.instructions) {
+      if (!accountKeys[ix.programIdIndex].equals(PROGRAM.programId)) {
+        continue;
+      }
+      const data = anchor.utils.bytes.bs58.decode(ix.data);
+      const event = PROGRAM.coder.events.decode(
+        anchor.utils.bytes.base64.encode(data.slice(8))
+      );
+      if (event) {
+        events.push(event);
+      }
+    }
+  }
+  return events;
+}
+
+export async function cpi_events(signature: string, name: string | null = null) {
+  return (await program_events(signature))
+    .filter((event) => name === null || event.name === name)
+    .map((event) => event.data);
+}
+
+function borsh_string(value: string) {
+  const bytes = Buffer.from(value);
+  const length = Buffer.alloc(4);
+  length.writeUInt32LE(bytes.length);
+  return Buffer.concat([length, bytes]);
+}
+
+function borsh_option(value: Buffer | null) {
+  return value === null
+    ? Buffer.from([0])
+    : Buffer.concat([Buffer.from([1]), value]);
+}
+
+function borsh_u64(value: anchor.BN) {
+  return value.toArrayLike(Buffer, "le", 8);
+}
+
+function enum_index(value: object, variants: string[]) {
+  return variants.indexOf(Object.keys(value)[0]);
+}
+
+/**
+ *  Computes the File policy hash off-chain, replaying the RolesChanged and RulesChanged events
+ *  of the File: hash = SHA-256(hash || action || leaf), with the leaf hashing the policy fields.
+ */
+export async function policy_hash(fileId: PublicKey = FILE_ID) {
+  const signatures = await PROVIDER.connection.getSignaturesForAddress(
+    await file_pda(fileId),
+    {},
+    "confirmed"
+  );
+  let hash = Buffer.alloc(32);
+  for (const { signature, err } of signatures.reverse()) {
+    if (err) {
+      continue;
+    }
+    for (const { name, data } of await program_events(signature)) {
+      if (!data.fileId || !data.fileId.equals(fileId)) {
+        continue;
+      }
+      let leaf: Buffer;
+      if (name === "RolesChanged") {
+        leaf = createHash("sha256")
+          .update("role")
+          .update(borsh_string(data.roleName))
+          .update(borsh_option(data.address ? data.address.toBuffer() : null))
+          .update(Buffer.from([enum_index(data.addressType, ["wallet", "collection"])]))
+          .update(borsh_option(data.expiresAt ? borsh_u64(data.expiresAt) : null))
+          .digest();
+      } else if (name === "RulesChanged") {
+        leaf = createHash("sha256")
+          .update("rule")
+          .update(Buffer.from([data.namespace]))
+          .update(borsh_string(data.role))
+          .update(borsh_string(data.resource))
+          .update(borsh_string(data.permission))
+          .update(borsh_u64(data.permissions))
+          .update(borsh_option(data.expiresAt ? borsh_u64(data.expiresAt) : null))
+          .digest();
+      } else {
+        continue;
+      }
+      const action = enum_index(data.action, ["created", "deleted", "updated"]);
+      hash = createHash("sha256")
+        .update(hash)
+        .update(Buffer.from([action]))
+        .update(leaf)
+        .digest();
+    }
+  }
+  return hash;
+}
+
+/**
+ *  Decodes the return data of the program from the (simulated) transaction logs.
+ */
+export function return_data(logs: string[], typeName: string) {
+  const prefix = `Program return: ${PROGRAM.programId.toBase58()} `;
+  const log = logs.find((log) => log.startsWith(prefix));
+  return PROGRAM.coder.types.decode(
+    typeName,
+    Buffer.from(log.slice(prefix.length), "base64")
+  );
+}
+
 export async function safe_airdrop(
   connection: anchor.web3.Connection,
   destination: anchor.web3.PublicKey,
@@ -24,6 +24,15 @@ export const ANOTHER_WALLET: anchor.web3.Keypair =
 export const FILE_ID = anchor.web3.Keypair.generate().publicKey;
 
 export const RECOVERY_KEYPAIR = anchor.web3.Keypair.generate();
+
+// Legacy File, Role and Rule (stored with their original layouts and seeds) are preloaded on the
+// validator from tests/fixtures (see Anchor.toml), so their File id and authority are fixed.
+export const LEGACY_FILE_ID = new anchor.web3.PublicKey(
+  new Uint8Array(32).fill(28)
+);
+export const LEGACY_AUTHORITY = anchor.web3.Keypair.fromSeed(
+  new Uint8Array(32).fill(28)
+);
 export const METADATA_PROGRAM_ID = new anchor.web3.PublicKey(
   "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
 );
@@ -36,8 +45,23 @@ export enum namespaces {
   AddRuleResourcePerm = 4,
   DeleteRuleNSRole = 5,
   DeleteRuleResourcePerm = 6,
+  UpdateAssignRole = 7,
+  UpdateRuleNSRole = 8,
+  UpdateRuleResourcePerm = 9,
+  UpdateMetadata = 10,
+  UpdateFile = 11,
+  DeleteFile = 12,
+  UpdateCache = 13,
+  ReviewRequest = 14,
 }
 
+export enum registryKinds {
+  Roles = 0,
+  Rules = 1,
+}
+
+export const REGISTRY_PAGE_SIZE = 32;
+
 export enum accountTypes {
   Basic = 0,
   Free = 1,